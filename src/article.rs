@@ -1,6 +1,9 @@
-use crate::converter;
-use chrono::Datelike;
-use failure::Error;
+use crate::blog::BuildOptions;
+use crate::config;
+use crate::config::Config;
+use crate::converter::{self, Highlighter};
+use chrono::{Datelike, TimeZone};
+use failure::{Error, Fail};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs::File;
@@ -12,44 +15,312 @@ use std::rc::Rc;
 pub struct Article {
     title: String,
     pub date: chrono::NaiveDate,
-    tags: Vec<String>,
+    pub datetime: chrono::DateTime<chrono::FixedOffset>,
+    updated: Option<chrono::NaiveDate>,
+    pub updated_datetime: Option<chrono::DateTime<chrono::FixedOffset>>,
+    pub tags: Vec<Tag>,
+    pub authors: Vec<ArticleAuthor>,
+    pub series: Option<String>,
+    series_order: Option<i64>,
     body: String,
     pub html: String,
+    toc: converter::Toc,
+    excerpt: Option<String>,
+    word_count: usize,
+    char_count: usize,
+    reading_minutes: u32,
     pub path: PathBuf,
+    slug: String,
+    draft: bool,
+    pub layout: Option<String>,
+    pub lang: Option<String>,
+    // Articles sharing a translation key are translations of each other.
+    pub translation_key: Option<String>,
+    pub extra: HashMap<String, serde_json::Value>,
+    // The directory of a bundle article, which is `index.md` with other files to be published.
+    #[serde(skip)]
+    pub bundle_dir: Option<PathBuf>,
+}
+
+// A tag of an article normalized by `blog.tags` config.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tag {
+    pub key: String,
+    // The URL-safe form of the key, used for paths of tag pages.
+    pub slug: String,
+    pub name: String,
+    pub description: Option<String>,
+}
+
+// A link to an article from another page, such as `prev_article`, which omits large attributes
+// like `html`.
+#[derive(Debug, Serialize)]
+pub struct ArticleLink<'a> {
+    title: &'a str,
+    path: &'a PathBuf,
+    date: chrono::NaiveDate,
+}
+
+// An author of an article with the profile in `blog.authors` config.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ArticleAuthor {
+    pub key: String,
+    // The URL-safe form of the key, and the path of the first page of the author.
+    pub slug: String,
+    pub path: String,
+    #[serde(flatten)]
+    pub profile: config::Author,
+}
+
+impl ArticleAuthor {
+    pub fn new(key: &str, profile: &config::Author) -> Self {
+        let slug = converter::slugify(key);
+        ArticleAuthor {
+            key: key.to_string(),
+            path: author_path(&slug, 1),
+            slug: slug,
+            profile: profile.clone(),
+        }
+    }
+}
+
+// Front matter keys read by Salmon, which cannot be used for other purposes like taxonomies.
+pub const FRONT_MATTER_KEYS: &[&str] = &[
+    "title",
+    "date",
+    "updated",
+    "tags",
+    "author",
+    "authors",
+    "series",
+    "series_order",
+    "slug",
+    "draft",
+    "layout",
+    "lang",
+    "translation_key",
+];
+
+#[derive(Debug, Deserialize)]
+struct FrontMatter {
+    title: String,
+    date: ArticleDate,
+    #[serde(default)]
+    updated: Option<ArticleDate>,
+    #[serde(default)]
+    tags: Tags,
+    #[serde(default)]
+    author: Option<String>,
+    #[serde(default)]
+    authors: Vec<String>,
+    #[serde(default)]
+    series: Option<String>,
+    #[serde(default)]
+    series_order: Option<i64>,
+    #[serde(default)]
+    slug: Option<String>,
+    #[serde(default)]
+    draft: bool,
+    #[serde(default)]
+    layout: Option<String>,
+    #[serde(default)]
+    lang: Option<String>,
+    #[serde(default)]
+    translation_key: Option<String>,
+    // Keys which are not known by Salmon are kept to be used in layouts as `article.extra.*`.
+    #[serde(flatten)]
+    extra: HashMap<String, serde_json::Value>,
+}
+
+// Both `tags: foo, bar` (the legacy style) and a YAML sequence are acceptable.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Tags {
+    List(Vec<String>),
+    CommaSeparated(String),
+}
+
+// `date` accepts `2019-06-23`, `2019-06-23 21:00` and `2019-06-23T21:00:00+09:00`.
+// A value without offset is regarded as a local time in the timezone of the blog.
+#[derive(Debug, PartialEq)]
+enum ArticleDate {
+    Local(chrono::NaiveDateTime),
+    WithOffset(chrono::DateTime<chrono::FixedOffset>),
+}
+
+#[derive(Debug, Fail)]
+pub enum FrontMatterError {
+    #[fail(display = "Front matter is not found. Line 1 must be `---`.")]
+    NotFound,
+    #[fail(display = "Front matter is not closed. Put `---` after line {}.", line)]
+    NotClosed { line: usize },
+    #[fail(display = "Invalid front matter at line {}: {}", line, message)]
+    Invalid { line: usize, message: String },
+}
+
+impl Article {
+    // The URL of the directory where the article is published, such as `/2019/06/23/example/`.
+    pub fn output_dir(&self) -> String {
+        output_dir(&self.path.to_string_lossy())
+    }
+
+    // Relative paths given to `embed_code` helper in the article, which refer to files in its
+    // bundle.
+    pub fn embedded_paths(&self) -> Vec<&str> {
+        find_embedded_paths(&self.body)
+    }
+
+    // Files in the bundle directory of the article except `index.md`.
+    pub fn bundle_files(&self) -> Result<Vec<PathBuf>, Error> {
+        let bundle_dir = match &self.bundle_dir {
+            Some(d) => d,
+            None => return Ok(Vec::new()),
+        };
+        let mut files = Vec::new();
+        for entry in glob::glob(bundle_dir.join("**/*").to_str().unwrap())? {
+            let path = entry?;
+            if path.is_file() && path != bundle_dir.join("index.md") {
+                files.push(path);
+            }
+        }
+        Ok(files)
+    }
+
+    pub fn link(&self) -> ArticleLink<'_> {
+        ArticleLink {
+            title: &self.title,
+            path: &self.path,
+            date: self.date,
+        }
+    }
+
+    // The time when the article was modified at last. It falls back to `datetime` if the
+    // article has no `updated` in its front matter.
+    pub fn last_modified(&self) -> chrono::DateTime<chrono::FixedOffset> {
+        self.updated_datetime.unwrap_or(self.datetime)
+    }
 }
 
 pub type ArticlesByTag = HashMap<String, Vec<Rc<Article>>>;
 pub type ArticlesByYear = HashMap<i32, Vec<Rc<Article>>>;
+pub type ArticlesByMonth = HashMap<(i32, u32), Vec<Rc<Article>>>;
+pub type ArticlesBySeries = HashMap<String, Vec<Rc<Article>>>;
+pub type ArticlesByAuthor = HashMap<String, Vec<Rc<Article>>>;
+pub type LoadedArticles = (ArticlesBySeries, ArticlesByAuthor, Vec<Rc<Article>>);
 
 pub fn load_articles(
     src_dir: &PathBuf,
-) -> Result<(ArticlesByTag, ArticlesByYear, Vec<Rc<Article>>), Error> {
-    let mut articles_by_tag = ArticlesByTag::new();
-    let mut articles_by_year = ArticlesByYear::new();
-    let mut sorted_articles = Vec::new();
+    config: &Config,
+    highlighter: &Highlighter,
+    options: &BuildOptions,
+) -> Result<LoadedArticles, Error> {
+    let blog_config = match config {
+        Config::V1(ref c) => &c.blog,
+    };
+    let timezone = blog_config.timezone_offset()?;
+    let now = chrono::Utc::now().with_timezone(&timezone);
+
+    let mut articles_by_series = ArticlesBySeries::new();
+    let mut articles_by_author = ArticlesByAuthor::new();
+    let mut sorted_articles: Vec<Rc<Article>> = Vec::new();
+    // Authors with the same slug would share a page.
+    let mut author_keys: Vec<_> = blog_config.authors.keys().collect();
+    author_keys.sort();
+    let mut author_slugs: HashMap<String, &String> = HashMap::new();
+    for key in author_keys {
+        if !key.chars().any(char::is_alphanumeric) {
+            return Err(failure::format_err!(
+                "Author `{}` in `blog.authors` config has no characters for its slug.",
+                key
+            ));
+        }
+        if let Some(other) = author_slugs.insert(converter::slugify(key), key) {
+            return Err(failure::format_err!(
+                "Authors `{}` and `{}` in `blog.authors` config have the same slug `{}`. Rename one of them.",
+                other,
+                key,
+                converter::slugify(key)
+            ));
+        }
+    }
+    // Keys of tags by their slugs, to detect different tags like `C` and `C++` sharing a tag page.
+    let mut tag_keys: HashMap<String, String> = HashMap::new();
+    // Names of series by their slugs for the same purpose.
+    let mut series_names: HashMap<String, String> = HashMap::new();
 
     let article_dir_glob = glob::glob(&src_dir.join("articles/**/*.md").to_str().unwrap())?;
     for entry in article_dir_glob {
         match entry {
             Ok(path) => {
-                let article = Rc::new(load_article(src_dir, &path)?);
+                if is_in_bundle(&path, &src_dir.join("articles")) {
+                    log::debug!("Skipped a markdown file in a bundle: {:?}", &path);
+                    continue;
+                }
+                let article = Rc::new(load_article(
+                    src_dir,
+                    &path,
+                    blog_config,
+                    highlighter,
+                    &timezone,
+                )?);
+                if article.draft && !options.drafts {
+                    log::info!("Skipped a draft article: {:?}", &path);
+                    continue;
+                }
+                if article.datetime > now && !options.future {
+                    log::info!(
+                        "Skipped an article scheduled at {}: {:?}",
+                        article.datetime.to_rfc3339(),
+                        &path
+                    );
+                    continue;
+                }
+                if let Some(a) = sorted_articles.iter().find(|a| a.path == article.path) {
+                    return Err(failure::format_err!(
+                        "Articles \"{}\" and \"{}\" have the same path {:?}.",
+                        a.title,
+                        article.title,
+                        article.path
+                    ));
+                }
                 for tag in article.tags.iter() {
-                    if !articles_by_tag.contains_key(tag.as_str()) {
-                        articles_by_tag.insert(tag.clone(), Vec::new());
+                    let key = tag_keys
+                        .entry(tag.slug.clone())
+                        .or_insert_with(|| tag.key.clone());
+                    if normalize_tag(key) != normalize_tag(&tag.key) {
+                        return Err(failure::format_err!(
+                            "Tags `{}` and `{}` have the same slug `{}` in {:?}. Declare one of them with another `slug` in `blog.tags` config.",
+                            key,
+                            tag.key,
+                            tag.slug,
+                            path
+                        ));
                     }
-                    articles_by_tag
-                        .get_mut(tag.as_str())
-                        .unwrap()
+                }
+                if let Some(ref series) = article.series {
+                    let name = series_names
+                        .entry(series_slug(series))
+                        .or_insert_with(|| series.clone());
+                    if name != series {
+                        return Err(failure::format_err!(
+                            "Series `{}` and `{}` have the same slug `{}` in {:?}. Rename one of them.",
+                            name,
+                            series,
+                            series_slug(series),
+                            path
+                        ));
+                    }
+                    articles_by_series
+                        .entry(series.clone())
+                        .or_default()
                         .push(article.clone());
                 }
-                let article_year = article.date.year();
-                if !articles_by_year.contains_key(&article_year) {
-                    articles_by_year.insert(article_year, Vec::new());
+                for author in article.authors.iter() {
+                    articles_by_author
+                        .entry(author.key.clone())
+                        .or_default()
+                        .push(article.clone());
                 }
-                articles_by_year
-                    .get_mut(&article_year)
-                    .unwrap()
-                    .push(article.clone());
                 sorted_articles.push(article.clone());
 
                 log::debug!("Article \"{}\" has been loaded.", &article.title);
@@ -57,113 +328,903 @@ pub fn load_articles(
             Err(e) => return Err(failure::format_err!("{:?}", e)),
         }
     }
-    sorted_articles.sort_by_key(|a| std::cmp::Reverse(a.date));
-    for (_, articles) in articles_by_tag.iter_mut() {
-        articles.sort_by_key(|a| std::cmp::Reverse(a.date));
+    sorted_articles.sort_by_key(|a| std::cmp::Reverse(a.datetime));
+    for (_, articles) in articles_by_author.iter_mut() {
+        articles.sort_by_key(|a| std::cmp::Reverse(a.datetime));
     }
-    for (_, articles) in articles_by_year.iter_mut() {
-        articles.sort_by_key(|a| std::cmp::Reverse(a.date));
+    // Parts of a series are sorted by `series_order`, and then by date in ascending order.
+    // Parts without `series_order` come after ordered ones.
+    for (_, articles) in articles_by_series.iter_mut() {
+        articles.sort_by_key(|a| (a.series_order.is_none(), a.series_order, a.datetime));
     }
-    Ok((articles_by_tag, articles_by_year, sorted_articles))
+    check_bundle_files(&sorted_articles)?;
+    Ok((articles_by_series, articles_by_author, sorted_articles))
 }
 
-fn load_article(src_dir: &PathBuf, article_path: &PathBuf) -> Result<Article, Error> {
+// Bundles share a directory when the permalink is not directory style like
+// `/blog/:year-:slug.html`, so their files must not be published to the same path.
+fn check_bundle_files(articles: &[Rc<Article>]) -> Result<(), Error> {
+    let mut src_paths: HashMap<String, PathBuf> = HashMap::new();
+    for article in articles.iter() {
+        let bundle_dir = match &article.bundle_dir {
+            Some(d) => d,
+            None => continue,
+        };
+        for src_path in article.bundle_files()? {
+            let dest_path = format!(
+                "{}{}",
+                article.output_dir(),
+                src_path.strip_prefix(bundle_dir)?.to_string_lossy()
+            );
+            if let Some(other) = src_paths.insert(dest_path.clone(), src_path.clone()) {
+                return Err(failure::format_err!(
+                    "Bundle files {:?} and {:?} are published to the same path {}. Rename one of them, or use a permalink ending with `/`.",
+                    other,
+                    src_path,
+                    dest_path
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+// Group articles by tag, year and month. Each group keeps the order of `sorted_articles`.
+pub fn group_articles(
+    sorted_articles: &[Rc<Article>],
+) -> (ArticlesByTag, ArticlesByYear, ArticlesByMonth) {
+    let mut articles_by_tag = ArticlesByTag::new();
+    let mut articles_by_year = ArticlesByYear::new();
+    let mut articles_by_month = ArticlesByMonth::new();
+    for article in sorted_articles.iter() {
+        for tag in article.tags.iter() {
+            articles_by_tag
+                .entry(tag.slug.clone())
+                .or_default()
+                .push(article.clone());
+        }
+        articles_by_year
+            .entry(article.date.year())
+            .or_default()
+            .push(article.clone());
+        articles_by_month
+            .entry((article.date.year(), article.date.month()))
+            .or_default()
+            .push(article.clone());
+    }
+    (articles_by_tag, articles_by_year, articles_by_month)
+}
+
+fn load_article(
+    src_dir: &PathBuf,
+    article_path: &PathBuf,
+    blog_config: &config::Blog,
+    highlighter: &Highlighter,
+    timezone: &chrono::FixedOffset,
+) -> Result<Article, Error> {
     let mut file = File::open(article_path)?;
     let mut source = String::new();
     file.read_to_string(&mut source)?;
 
-    let (title, date, tags, body) = decompose_source(&source)?;
-    let html = converter::convert_to_html(&body);
-
-    Ok(Article {
-        title: title,
-        date: date,
-        tags: tags,
-        body: body,
-        html: html,
-        path: PathBuf::from("/").join(
+    let (front_matter, body) = decompose_source::<FrontMatter>(&source)
+        .map_err(|e| failure::format_err!("{} in {:?}", e, article_path))?;
+    // `articles/index.md` is a plain article because its bundle would be the whole `articles/`.
+    let bundle_dir = match article_path.parent() {
+        Some(dir)
+            if article_path.file_name().unwrap() == "index.md"
+                && dir != src_dir.join("articles").as_path() =>
+        {
+            Some(dir.to_path_buf())
+        }
+        _ => None,
+    };
+    let (word_count, char_count) = count_words(&converter::extract_text(&body));
+    let speed = &blog_config.reading_speed;
+    let reading_minutes = (word_count as f64 / f64::from(speed.words_per_minute.max(1))
+        + char_count as f64 / f64::from(speed.characters_per_minute.max(1)))
+    .ceil()
+    .max(1.0) as u32;
+    let datetime = front_matter.date.resolve(timezone);
+    let updated_datetime = front_matter.updated.map(|u| u.resolve(timezone));
+    let mut tags: Vec<Tag> = Vec::new();
+    for tag in front_matter.tags.into_vec() {
+        let tag = resolve_tag(&tag, &blog_config.tags)
+            .map_err(|e| failure::format_err!("{} in {:?}", e, article_path))?;
+        if !tags.iter().any(|t| t.slug == tag.slug) {
+            tags.push(tag);
+        }
+    }
+    let mut authors = Vec::new();
+    for key in front_matter.author.into_iter().chain(front_matter.authors) {
+        let profile = blog_config.authors.get(&key).ok_or_else(|| {
+            failure::format_err!(
+                "Author `{}` in {:?} is not defined in `blog.authors` config.",
+                key,
+                article_path
+            )
+        })?;
+        // The same author in both `author` and `authors` is listed once.
+        if !authors.iter().any(|a: &ArticleAuthor| a.key == key) {
+            authors.push(ArticleAuthor::new(&key, profile));
+        }
+    }
+    if let Some(ref series) = front_matter.series {
+        if !series.chars().any(char::is_alphanumeric) {
+            return Err(failure::format_err!(
+                "Series `{}` in {:?} has no characters for its slug.",
+                series,
+                article_path
+            ));
+        }
+    }
+    if let Some(ref lang) = front_matter.lang {
+        if !blog_config.languages.contains_key(lang) {
+            return Err(failure::format_err!(
+                "Language `{}` in {:?} is not defined in `blog.languages` config.",
+                lang,
+                article_path
+            ));
+        }
+    }
+    // A bundle is named after its directory.
+    let slug = match (front_matter.slug, &bundle_dir) {
+        (Some(s), _) => s,
+        (None, Some(d)) => d.file_name().unwrap().to_string_lossy().to_string(),
+        (None, None) => article_path
+            .file_stem()
+            .unwrap()
+            .to_string_lossy()
+            .to_string(),
+    };
+    validate_slug(&slug).map_err(|e| failure::format_err!("{} in {:?}", e, article_path))?;
+    let path = match (&blog_config.permalink, &bundle_dir) {
+        (Some(p), _) => PathBuf::from(resolve_permalink(p, &datetime, &slug)?),
+        (None, Some(d)) => PathBuf::from(format!(
+            "/{}/",
+            d.strip_prefix(src_dir.join("articles/"))?.to_string_lossy()
+        )),
+        (None, None) => PathBuf::from("/").join(
             article_path
                 .strip_prefix(src_dir.join("articles/"))?
                 .with_extension("html")
                 .to_path_buf(),
         ),
+    };
+    // Relative links in a bundle refer to files published next to it.
+    let link_base = bundle_dir
+        .as_ref()
+        .map(|_| output_dir(&path.to_string_lossy()));
+    let (html, toc, excerpt) = converter::convert_to_html(
+        &body,
+        &blog_config.markdown,
+        highlighter,
+        link_base.as_ref(),
+    );
+
+    Ok(Article {
+        title: front_matter.title,
+        date: datetime.naive_local().date(),
+        datetime: datetime,
+        updated: updated_datetime.map(|u| u.naive_local().date()),
+        updated_datetime: updated_datetime,
+        tags,
+        authors,
+        series: front_matter.series,
+        series_order: front_matter.series_order,
+        body: body,
+        html: html,
+        toc,
+        excerpt,
+        word_count,
+        char_count,
+        reading_minutes,
+        path,
+        slug,
+        draft: front_matter.draft,
+        layout: front_matter.layout,
+        lang: front_matter.lang,
+        translation_key: front_matter.translation_key,
+        extra: front_matter.extra,
+        bundle_dir,
     })
 }
 
-fn decompose_source(
-    source: &str,
-) -> Result<(String, chrono::NaiveDate, Vec<String>, String), Error> {
-    let mut title = String::new();
-    let mut date = chrono::NaiveDate::parse_from_str("2019-04-01", "%Y-%m-%d").unwrap();
-    let mut tags = Vec::new();
+// Markdown files in a bundle directory are not articles but files of the bundle. So are
+// `index.md` in a subdirectory of another bundle.
+fn is_in_bundle(path: &PathBuf, articles_dir: &PathBuf) -> bool {
+    let mut dir = path.parent();
+    while let Some(d) = dir {
+        if !d.starts_with(articles_dir) || d == articles_dir.as_path() {
+            break;
+        }
+        let index = d.join("index.md");
+        if index != *path && index.exists() {
+            return true;
+        }
+        dir = d.parent();
+    }
+    false
+}
+
+// The URL of a file referred by a relative path like `./example.rb` from an article, which is
+// published next to the article.
+pub fn resolve_relative_path(article_path: &str, relative_path: &str) -> String {
+    format!(
+        "{}{}",
+        output_dir(article_path),
+        relative_path.trim_start_matches("./")
+    )
+}
+
+fn output_dir(path: &str) -> String {
+    match path.rfind('/') {
+        Some(i) => path[..=i].to_string(),
+        None => "/".to_string(),
+    }
+}
+
+// Find string literals given to `embed_code` like `{{ embed_code "example.rb" }}`, except
+// absolute paths which refer to files in `codes/`.
+fn find_embedded_paths(body: &str) -> Vec<&str> {
+    let mut paths = Vec::new();
+    let mut rest = body;
+    while let Some(i) = rest.find("embed_code") {
+        rest = rest[i + "embed_code".len()..].trim_start();
+        if !rest.starts_with('"') {
+            continue;
+        }
+        if let Some(end) = rest[1..].find('"') {
+            let path = &rest[1..=end];
+            if !path.starts_with('/') && !paths.contains(&path) {
+                paths.push(path);
+            }
+        }
+    }
+    paths
+}
+
+// Split a source into its front matter and body. Pages also use this with their own front matter.
+pub fn decompose_source<T>(source: &str) -> Result<(T, String), FrontMatterError>
+where
+    T: serde::de::DeserializeOwned,
+{
+    let mut lines = source.lines();
+    match lines.next() {
+        Some(line) if line.trim() == "---" => { /* do nothing */ }
+        _ => return Err(FrontMatterError::NotFound),
+    }
+
+    let mut yaml = String::new();
+    let mut yaml_lines = 0;
+    let mut is_closed = false;
+    for line in &mut lines {
+        if line.trim() == "---" {
+            is_closed = true;
+            break;
+        }
+        yaml.push_str(line);
+        yaml.push('\n');
+        yaml_lines += 1;
+    }
+    if !is_closed {
+        return Err(FrontMatterError::NotClosed {
+            line: yaml_lines + 1,
+        });
+    }
+
+    let front_matter: T = serde_yaml::from_str(&yaml).map_err(|e| {
+        // Line numbers reported by serde_yaml are relative to the YAML part, which starts
+        // after the first `---` line.
+        let line = e.location().map(|l| l.line() + 1).unwrap_or(1);
+        let mut message = e.to_string();
+        if let Some(i) = message.rfind(" at line ") {
+            message.truncate(i);
+        }
+        FrontMatterError::Invalid { line, message }
+    })?;
+
     let mut body = String::with_capacity(source.len());
+    for line in lines {
+        body.push_str(line);
+        body.push('\n');
+    }
 
-    let mut line_number = 0;
-    for line in source.lines() {
-        match line_number {
-            0 | 4 => {
-                if line.trim() != "---" {
-                    return Err(failure::format_err!(
-                        "Invalid markdown metadata format error.\nLine {} is not `---`",
-                        line_number,
-                    ));
-                }
+    Ok((front_matter, body))
+}
+
+// Normalize a tag to the key in `blog.tags` config which it matches, ignoring cases and
+// separators like spaces and `_`. Keys are tried in sorted order so that the result does not
+// depend on the order of the config.
+fn resolve_tag(tag: &str, tag_configs: &HashMap<String, config::Tag>) -> Result<Tag, Error> {
+    let normalized = normalize_tag(tag);
+    let mut keys: Vec<_> = tag_configs.keys().collect();
+    keys.sort();
+    for key in keys {
+        let tag_config = &tag_configs[key];
+        if normalize_tag(key) == normalized
+            || tag_config
+                .aliases
+                .iter()
+                .any(|alias| normalize_tag(alias) == normalized)
+        {
+            let slug = match tag_config.slug {
+                Some(ref s) => s.clone(),
+                None => tag_slug(key)?,
+            };
+            validate_term_slug(&slug).map_err(|e| {
+                failure::format_err!(
+                    "{} It is the slug of tag `{}` in `blog.tags` config",
+                    e,
+                    key
+                )
+            })?;
+            return Ok(Tag {
+                key: key.clone(),
+                slug,
+                name: tag_config.name.clone().unwrap_or_else(|| key.clone()),
+                description: tag_config.description.clone(),
+            });
+        }
+    }
+    let slug = tag_slug(tag)?;
+    validate_term_slug(&slug).map_err(|e| {
+        failure::format_err!(
+            "{} Declare tag `{}` with another `slug` in `blog.tags` config",
+            e,
+            tag
+        )
+    })?;
+    Ok(Tag {
+        key: tag.to_string(),
+        slug,
+        name: tag.to_string(),
+        description: None,
+    })
+}
+
+// Tags and terms of taxonomies cannot have `index` as their slug, because their list pages are
+// published as `index.html` in the same directory, like `/tags/index.html`.
+pub fn validate_term_slug(slug: &str) -> Result<(), Error> {
+    validate_slug(slug)?;
+    if slug == "index" {
+        return Err(failure::format_err!(
+            "Slug `index` is used by the page listing all terms."
+        ));
+    }
+    Ok(())
+}
+
+pub fn normalize_tag(tag: &str) -> String {
+    tag.trim()
+        .to_lowercase()
+        .split(|c: char| c.is_whitespace() || c == '-' || c == '_')
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+// The URL-safe form of a series name, used for the path of its page like `/series/<slug>.html`.
+pub fn series_slug(series: &str) -> String {
+    converter::slugify(series)
+}
+
+// The URL of the page of a series.
+pub fn series_path(slug: &str) -> String {
+    format!("/series/{}.html", slug)
+}
+
+// The URL of the `page_number`th page (1 origin) of an author.
+pub fn author_path(slug: &str, page_number: usize) -> String {
+    if page_number == 1 {
+        format!("/authors/{}.html", slug)
+    } else {
+        format!("/authors/{}/page/{}.html", slug, page_number)
+    }
+}
+
+// A tag made of symbols only like `+` has no slug, so it must be declared with `slug`.
+fn tag_slug(tag: &str) -> Result<String, Error> {
+    if !tag.chars().any(char::is_alphanumeric) {
+        return Err(failure::format_err!(
+            "Tag `{}` has no characters for its slug, so declare it with `slug` in `blog.tags` config",
+            tag
+        ));
+    }
+    Ok(converter::slugify(tag))
+}
+
+// Count space delimited words and CJK characters separately because CJK text has no spaces
+// between words.
+fn count_words(text: &str) -> (usize, usize) {
+    let mut word_count = 0;
+    let mut char_count = 0;
+    let mut in_word = false;
+    for c in text.chars() {
+        if is_cjk(c) {
+            char_count += 1;
+            in_word = false;
+        } else if c.is_whitespace() || is_cjk_punctuation(c) {
+            in_word = false;
+        } else if !in_word {
+            word_count += 1;
+            in_word = true;
+        }
+    }
+    (word_count, char_count)
+}
+
+fn is_cjk(c: char) -> bool {
+    match c as u32 {
+        0x3040..=0x30FF // Hiragana and Katakana
+        | 0x31F0..=0x31FF // Katakana Phonetic Extensions
+        | 0x3400..=0x4DBF // CJK Unified Ideographs Extension A
+        | 0x4E00..=0x9FFF // CJK Unified Ideographs
+        | 0xAC00..=0xD7AF // Hangul Syllables
+        | 0xF900..=0xFAFF // CJK Compatibility Ideographs
+        | 0xFF66..=0xFF9F // Halfwidth Katakana
+        | 0x20000..=0x2FFFF => true, // CJK Unified Ideographs Extension B and later
+        _ => false,
+    }
+}
+
+fn is_cjk_punctuation(c: char) -> bool {
+    match c as u32 {
+        0x3000..=0x303F // CJK Symbols and Punctuation
+        | 0xFF01..=0xFF65 => true, // Fullwidth forms
+        _ => false,
+    }
+}
+
+// A slug is a segment of URLs, so it must not be empty, contain `/` or move to parent directories.
+fn validate_slug(slug: &str) -> Result<(), Error> {
+    if slug.is_empty() || slug == "." || slug == ".." || slug.contains(&['/', '\\'][..]) {
+        return Err(failure::format_err!(
+            "Slug `{}` must not be empty, `.`, `..` or contain `/`.",
+            slug
+        ));
+    }
+    Ok(())
+}
+
+// Build a path of an article from a pattern like `/:year/:month/:day/:slug/`.
+// A path terminated by `/` is written as `index.html` in the directory.
+fn resolve_permalink(
+    pattern: &str,
+    datetime: &chrono::DateTime<chrono::FixedOffset>,
+    slug: &str,
+) -> Result<String, Error> {
+    if !pattern.starts_with('/') {
+        return Err(failure::format_err!(
+            "Permalink `{}` must start with `/`.",
+            pattern
+        ));
+    }
+
+    let mut path = String::with_capacity(pattern.len() + slug.len());
+    let mut rest = pattern;
+    while let Some(i) = rest.find(':') {
+        path.push_str(&rest[..i]);
+        rest = &rest[i + 1..];
+        let end = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        match &rest[..end] {
+            "year" => path.push_str(&datetime.format("%Y").to_string()),
+            "month" => path.push_str(&datetime.format("%m").to_string()),
+            "day" => path.push_str(&datetime.format("%d").to_string()),
+            "slug" => path.push_str(slug),
+            placeholder => {
+                return Err(failure::format_err!(
+                    "Unknown placeholder `:{}` in permalink `{}`.",
+                    placeholder,
+                    pattern
+                ))
             }
-            1..=3 => {
-                let trimmed_line = line.trim();
-                if trimmed_line.starts_with("title:") {
-                    let v: Vec<&str> = trimmed_line.split(':').collect();
-                    match v.get(1) {
-                        Some(s) => title = s.trim().to_string(),
-                        None => {
-                            return Err(failure::format_err!(
-                                "{} does not have any value",
-                                v.get(0).unwrap()
-                            ))
-                        }
-                    }
-                } else if trimmed_line.starts_with("date:") {
-                    let v: Vec<&str> = trimmed_line.split(':').collect();
-                    match v.get(1) {
-                        Some(s) => {
-                            date = chrono::NaiveDate::parse_from_str(s.trim(), "%Y-%m-%d")?;
-                        }
-                        None => {
-                            return Err(failure::format_err!(
-                                "{} does not have any value",
-                                v.get(0).unwrap()
-                            ))
-                        }
-                    }
-                } else if trimmed_line.starts_with("tags:") {
-                    let v: Vec<&str> = trimmed_line.split(':').collect();
-                    match v.get(1) {
-                        Some(s) => {
-                            for tag in s.trim().split(',') {
-                                tags.push(tag.trim().to_string());
-                            }
-                        }
-                        None => {
-                            return Err(failure::format_err!(
-                                "{} does not have any value",
-                                v.get(0).unwrap()
-                            ))
-                        }
-                    }
-                } else {
-                    return Err(failure::format_err!(
-                        "Invalid markdown metadate element error.\n`{}` is not supported.",
-                        line
-                    ));
-                }
+        }
+        rest = &rest[end..];
+    }
+    path.push_str(rest);
+
+    Ok(path)
+}
+
+impl ArticleDate {
+    fn parse(s: &str) -> Option<Self> {
+        let s = s.trim();
+        if let Ok(d) = chrono::DateTime::parse_from_rfc3339(s) {
+            return Some(ArticleDate::WithOffset(d));
+        }
+        for format in &[
+            "%Y-%m-%dT%H:%M:%S",
+            "%Y-%m-%d %H:%M:%S",
+            "%Y-%m-%dT%H:%M",
+            "%Y-%m-%d %H:%M",
+        ] {
+            if let Ok(d) = chrono::NaiveDateTime::parse_from_str(s, format) {
+                return Some(ArticleDate::Local(d));
             }
-            _ => {
-                body.push_str(line);
-                body.push_str("\n");
+        }
+        chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d")
+            .ok()
+            .map(|d| ArticleDate::Local(d.and_hms(0, 0, 0)))
+    }
+
+    fn resolve(&self, timezone: &chrono::FixedOffset) -> chrono::DateTime<chrono::FixedOffset> {
+        match self {
+            ArticleDate::Local(d) => timezone.from_local_datetime(d).unwrap(),
+            ArticleDate::WithOffset(d) => *d,
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ArticleDate {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        // Return errors from a visitor to let serde_yaml report the position of the value.
+        struct ArticleDateVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for ArticleDateVisitor {
+            type Value = ArticleDate;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a date like 2019-06-23 or a datetime like 2019-06-23T21:00:00+09:00")
             }
+
+            fn visit_str<E>(self, v: &str) -> Result<ArticleDate, E>
+            where
+                E: serde::de::Error,
+            {
+                ArticleDate::parse(v)
+                    .ok_or_else(|| E::invalid_value(serde::de::Unexpected::Str(v), &self))
+            }
+        }
+
+        deserializer.deserialize_str(ArticleDateVisitor)
+    }
+}
+
+impl Tags {
+    fn into_vec(self) -> Vec<String> {
+        match self {
+            Tags::List(tags) => tags,
+            Tags::CommaSeparated(s) => s
+                .split(',')
+                .map(|t| t.trim().to_string())
+                .filter(|t| !t.is_empty())
+                .collect(),
         }
-        line_number += 1;
     }
+}
+
+impl Default for Tags {
+    fn default() -> Self {
+        Tags::List(Vec::new())
+    }
+}
+
+#[test]
+fn test_decompose_source() {
+    let (front_matter, body) = decompose_source::<FrontMatter>(
+        "---\ntitle: 'Rust: a retrospective'\ndate: 2019-06-23\ntags: [rust, diary]\ncover: /images/cover.png\n---\n\n# Hello\n",
+    )
+    .unwrap();
+    assert_eq!("Rust: a retrospective", front_matter.title);
+    assert_eq!(
+        ArticleDate::Local(chrono::NaiveDate::from_ymd(2019, 6, 23).and_hms(0, 0, 0)),
+        front_matter.date
+    );
+    assert_eq!(vec!["rust", "diary"], front_matter.tags.into_vec());
+    assert_eq!(
+        Some(&serde_json::json!("/images/cover.png")),
+        front_matter.extra.get("cover")
+    );
+    assert_eq!("\n# Hello\n", body);
+
+    let (front_matter, _) = decompose_source::<FrontMatter>(
+        "---\ntitle: foo\ndate: 2019-06-23\ntags: rust, diary\n---\n",
+    )
+    .unwrap();
+    assert_eq!(vec!["rust", "diary"], front_matter.tags.into_vec());
+
+    match decompose_source::<FrontMatter>("title: foo\n") {
+        Err(FrontMatterError::NotFound) => { /* ok */ }
+        r => panic!("unexpected result: {:?}", r),
+    }
+    match decompose_source::<FrontMatter>("---\ntitle: foo\ndate: 2019-06-23\n") {
+        Err(FrontMatterError::NotClosed { line: 3 }) => { /* ok */ }
+        r => panic!("unexpected result: {:?}", r),
+    }
+    match decompose_source::<FrontMatter>("---\ntitle: foo\ndate: 2019-06-xx\n---\n") {
+        Err(FrontMatterError::Invalid { line, .. }) => assert_eq!(3, line),
+        r => panic!("unexpected result: {:?}", r),
+    }
+}
+
+#[test]
+fn test_article_date() {
+    let jst = chrono::FixedOffset::east(9 * 3600);
+    assert_eq!(
+        "2019-06-23T00:00:00+09:00",
+        ArticleDate::parse("2019-06-23")
+            .unwrap()
+            .resolve(&jst)
+            .to_rfc3339()
+    );
+    assert_eq!(
+        "2019-06-23T21:30:00+09:00",
+        ArticleDate::parse("2019-06-23 21:30")
+            .unwrap()
+            .resolve(&jst)
+            .to_rfc3339()
+    );
+    assert_eq!(
+        "2019-06-23T21:30:00-07:00",
+        ArticleDate::parse("2019-06-23T21:30:00-07:00")
+            .unwrap()
+            .resolve(&jst)
+            .to_rfc3339()
+    );
+    assert_eq!(None, ArticleDate::parse("2019-06-xx"));
+}
+
+#[test]
+fn test_resolve_tag() {
+    let mut tag_configs = HashMap::new();
+    tag_configs.insert(
+        "rust".to_string(),
+        config::Tag {
+            slug: None,
+            name: Some("Rust".to_string()),
+            description: Some("A systems programming language".to_string()),
+            aliases: vec!["rustlang".to_string(), "Rust Lang".to_string()],
+        },
+    );
+    tag_configs.insert(
+        "C++".to_string(),
+        config::Tag {
+            slug: Some("cpp".to_string()),
+            name: None,
+            description: None,
+            aliases: vec![],
+        },
+    );
+    tag_configs.insert(
+        "日記".to_string(),
+        config::Tag {
+            slug: Some("diary".to_string()),
+            name: None,
+            description: None,
+            aliases: vec![],
+        },
+    );
+
+    for t in &["rust", "Rust", "RustLang", "rust_lang", " rust  lang "] {
+        let tag = resolve_tag(t, &tag_configs).unwrap();
+        assert_eq!("rust", tag.key);
+        assert_eq!("rust", tag.slug);
+        assert_eq!("Rust", tag.name);
+        assert_eq!(
+            Some("A systems programming language".to_string()),
+            tag.description
+        );
+    }
+
+    let tag = resolve_tag("Static Site / Blog", &tag_configs).unwrap();
+    assert_eq!("Static Site / Blog", tag.key);
+    assert_eq!("static-site--blog", tag.slug);
+    assert_eq!("Static Site / Blog", tag.name);
+    assert_eq!(None, tag.description);
+
+    // Symbols are not ignored when matching, so `C` is not `C++`.
+    let tag = resolve_tag("c++", &tag_configs).unwrap();
+    assert_eq!(("C++", "cpp"), (tag.key.as_str(), tag.slug.as_str()));
+    let tag = resolve_tag("C", &tag_configs).unwrap();
+    assert_eq!(("C", "c"), (tag.key.as_str(), tag.slug.as_str()));
+
+    assert_eq!("diary", resolve_tag("日記", &tag_configs).unwrap().slug);
+    assert_eq!("写真", resolve_tag("写真", &tag_configs).unwrap().slug);
+    assert!(resolve_tag("+", &tag_configs).is_err());
+
+    assert!(resolve_tag("Index", &tag_configs).is_err());
+    tag_configs.get_mut("C++").unwrap().slug = Some("index".to_string());
+    assert!(resolve_tag("C++", &tag_configs).is_err());
+    tag_configs.get_mut("C++").unwrap().slug = Some("../cpp".to_string());
+    assert!(resolve_tag("C++", &tag_configs).is_err());
+}
+
+#[test]
+fn test_find_embedded_paths() {
+    assert_eq!(
+        vec!["example.rb", "./src/main.rs"],
+        find_embedded_paths(
+            "{{ embed_code \"example.rb\" }}\n\n{{embed_code \"/example.rb\"}}\n\n{{ embed_code \"./src/main.rs\" }} {{ embed_code \"example.rb\" }}\n"
+        )
+    );
+    assert!(find_embedded_paths("embed_code without a path, and {{ embed_code path }}").is_empty());
+    assert_eq!(
+        "/2019/06/23/example/src/main.rs",
+        resolve_relative_path("/2019/06/23/example/", "./src/main.rs")
+    );
+    assert_eq!(
+        "/blog/example.rb",
+        resolve_relative_path("/blog/2019-06-example.html", "example.rb")
+    );
+}
+
+#[test]
+fn test_count_words() {
+    assert_eq!((4, 0), count_words("Hopping bunnies are cute."));
+    assert_eq!((0, 7), count_words("うさぎが跳ねる。"));
+    assert_eq!((3, 4), count_words("Rust で static site を作る"));
+}
+
+#[test]
+fn test_resolve_permalink() {
+    let datetime = chrono::DateTime::parse_from_rfc3339("2019-06-23T21:00:00+09:00").unwrap();
+    assert_eq!(
+        "/2019/06/23/example/",
+        resolve_permalink("/:year/:month/:day/:slug/", &datetime, "example").unwrap()
+    );
+    assert_eq!(
+        "/blog/2019-06-example.html",
+        resolve_permalink("/blog/:year-:month-:slug.html", &datetime, "example").unwrap()
+    );
+    assert!(validate_slug("example").is_ok());
+    assert!(validate_slug("../../x").is_err());
+    assert!(validate_slug("..").is_err());
+    assert!(validate_slug("").is_err());
+    assert!(resolve_permalink("/:title/", &datetime, "example").is_err());
+    assert!(resolve_permalink(":slug/", &datetime, "example").is_err());
+}
+
+// An article with the least fields for tests of the modules using articles.
+#[cfg(test)]
+pub fn test_article(path: &str, datetime: &str, tags: &[&str]) -> Rc<Article> {
+    let tags: Vec<_> = tags
+        .iter()
+        .map(|t| serde_json::json!({"key": t, "slug": t, "name": t}))
+        .collect();
+    Rc::new(
+        serde_json::from_value(serde_json::json!({
+            "title": path,
+            "date": &datetime[..10],
+            "datetime": datetime,
+            "tags": tags,
+            "authors": [],
+            "body": "",
+            "html": "",
+            "toc": {"entries": [], "html": ""},
+            "word_count": 0,
+            "char_count": 0,
+            "reading_minutes": 1,
+            "path": path,
+            "slug": path,
+            "draft": false,
+            "extra": {},
+        }))
+        .unwrap(),
+    )
+}
+
+// A project in a temporary directory unique to the test, with files given as pairs of a path and
+// content.
+#[cfg(test)]
+fn test_project(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let src_dir = std::env::temp_dir().join(format!("salmon-test-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&src_dir);
+    for (path, content) in files {
+        let path = src_dir.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+    src_dir
+}
+
+#[test]
+fn test_is_in_bundle() {
+    let src_dir = test_project(
+        "is-in-bundle",
+        &[
+            ("articles/plain.md", ""),
+            ("articles/bundle/index.md", ""),
+            ("articles/bundle/notes.md", ""),
+            ("articles/bundle/sub/index.md", ""),
+            ("articles/dir/other.md", ""),
+        ],
+    );
+    let articles_dir = src_dir.join("articles");
+    let is_in_bundle = |path: &str| is_in_bundle(&articles_dir.join(path), &articles_dir);
+
+    assert!(!is_in_bundle("plain.md"));
+    assert!(!is_in_bundle("bundle/index.md"));
+    assert!(is_in_bundle("bundle/notes.md"));
+    assert!(is_in_bundle("bundle/sub/index.md"));
+    assert!(!is_in_bundle("dir/other.md"));
+
+    std::fs::remove_dir_all(src_dir).unwrap();
+}
+
+#[test]
+fn test_load_articles_with_bundles() {
+    let files = |permalink: &str| {
+        vec![
+            (
+                "salmon.yaml".to_string(),
+                format!(
+                    "version: '1'\nblog:\n  site_root: 'https://example.com'\n  permalink: '{}'\n",
+                    permalink
+                ),
+            ),
+            (
+                "articles/a/index.md".to_string(),
+                "---\ntitle: A\ndate: 2019-06-22\n---\n".to_string(),
+            ),
+            ("articles/a/cover.png".to_string(), "a".to_string()),
+            (
+                "articles/b/index.md".to_string(),
+                "---\ntitle: B\ndate: 2019-06-23\n---\n".to_string(),
+            ),
+            ("articles/b/cover.png".to_string(), "b".to_string()),
+        ]
+    };
+    let load = |name: &str, permalink: &str| {
+        let files = files(permalink);
+        let files: Vec<_> = files
+            .iter()
+            .map(|(p, c)| (p.as_str(), c.as_str()))
+            .collect();
+        let src_dir = test_project(name, &files);
+        let config = Config::load(&src_dir).unwrap();
+        let highlighter = Highlighter::load(&src_dir, &config::Highlight::default()).unwrap();
+        let loaded = load_articles(&src_dir, &config, &highlighter, &BuildOptions::default());
+        std::fs::remove_dir_all(src_dir).unwrap();
+        loaded
+    };
+
+    // Bundles are published in their own directories.
+    let (_, _, sorted_articles) = load("bundles", "/:year/:slug/").unwrap();
+    let dirs: Vec<_> = sorted_articles.iter().map(|a| a.output_dir()).collect();
+    assert_eq!(vec!["/2019/b/", "/2019/a/"], dirs);
+
+    // `cover.png` of both bundles would be published to `/blog/cover.png`.
+    let e = load("bundle-collision", "/blog/:year-:slug.html").unwrap_err();
+    assert!(e.to_string().contains("/blog/cover.png"), "{}", e);
+}
+
+#[test]
+fn test_load_articles_with_top_index() {
+    let src_dir = test_project(
+        "top-index",
+        &[
+            (
+                "salmon.yaml",
+                "version: '1'\nblog:\n  site_root: 'https://example.com'\n",
+            ),
+            (
+                "articles/index.md",
+                "---\ntitle: Top\ndate: 2019-06-22\n---\n",
+            ),
+            (
+                "articles/a/index.md",
+                "---\ntitle: A\ndate: 2019-06-23\n---\n",
+            ),
+            ("articles/a/cover.png", "a"),
+        ],
+    );
+    let config = Config::load(&src_dir).unwrap();
+    let highlighter = Highlighter::load(&src_dir, &config::Highlight::default()).unwrap();
+    let (_, _, sorted_articles) =
+        load_articles(&src_dir, &config, &highlighter, &BuildOptions::default()).unwrap();
+
+    // `articles/index.md` is not a bundle of all files in `articles/`.
+    let bundles: Vec<_> = sorted_articles
+        .iter()
+        .map(|a| (a.title.as_str(), a.bundle_files().unwrap().len()))
+        .collect();
+    assert_eq!(vec![("A", 1), ("Top", 0)], bundles);
+    assert_eq!("/", sorted_articles[1].output_dir());
 
-    Ok((title, date, tags, body))
+    std::fs::remove_dir_all(src_dir).unwrap();
 }
@@ -1,8 +1,9 @@
-use crate::article::ArticlesByTag;
 use crate::article::*;
 use crate::code::load_codes;
 use crate::code::Code;
 use crate::config::Config;
+use crate::converter::Highlighter;
+use crate::edition::{load_editions, Edition};
 use crate::layout::load_layouts;
 use crate::layout::{Layout, Layouts};
 use crate::page::load_pages;
@@ -11,7 +12,7 @@ use crate::paginator::Paginator;
 use crate::partial::load_partials;
 use crate::partial::Partial;
 use crate::resource::load_resources;
-use crate::resource::Resource;
+use crate::resource::{GeneratedResource, Resource};
 use crate::view_helper;
 use failure::Error;
 use handlebars::Handlebars;
@@ -21,9 +22,18 @@ use std::fs::File;
 use std::io::prelude::*;
 use std::path::PathBuf;
 use std::rc::Rc;
+use std::sync::Arc;
 
 type ViewItems = std::vec::Vec<serde_json::Map<String, handlebars::JsonValue>>;
 
+#[derive(Debug, Default)]
+pub struct BuildOptions {
+    // Build articles which have `draft: true` in their front matter.
+    pub drafts: bool,
+    // Build articles dated in the future.
+    pub future: bool,
+}
+
 #[derive(Debug)]
 pub struct Blog {
     config: Config,
@@ -31,26 +41,61 @@ pub struct Blog {
     src_dir: std::path::PathBuf,
     dest_dir: std::path::PathBuf,
 
-    articles_by_tag: ArticlesByTag,
-    articles_by_year: ArticlesByYear,
+    articles_by_series: ArticlesBySeries,
+    articles_by_author: ArticlesByAuthor,
     sorted_articles: Vec<Rc<Article>>,
+    editions: Vec<Edition>,
     layouts: Layouts,
     partials: Vec<Partial>,
     pages: Vec<Page>,
     codes: HashMap<PathBuf, Code>,
     pub resources: Vec<Resource>,
+    generated_resources: Vec<GeneratedResource>,
 }
 
 impl Blog {
-    pub fn init(src_dir: PathBuf, dest_dir: PathBuf, config: Config) -> Result<Self, Error> {
+    pub fn init(
+        src_dir: PathBuf,
+        dest_dir: PathBuf,
+        config: Config,
+        options: BuildOptions,
+    ) -> Result<Self, Error> {
         log::debug!("Start to load project files.");
 
-        let (articles_by_tag, articles_by_year, sorted_articles) = load_articles(&src_dir)?;
+        let highlight_config = match config {
+            Config::V1(ref c) => &c.blog.highlight,
+        };
+        let mut highlighter = Highlighter::load(&src_dir, highlight_config)?;
+        if let Some(ref cache) = highlight_config.cache {
+            highlighter.load_cache(src_dir.join(cache));
+        }
+        let highlighter = Arc::new(highlighter);
+        let (articles_by_series, articles_by_author, sorted_articles) =
+            load_articles(&src_dir, &config, &highlighter, &options)?;
         let layouts = load_layouts(&src_dir)?;
+        // Fail before building anything if an article or a page refers to an unknown layout.
+        for article in sorted_articles.iter() {
+            find_layout(&layouts, &article.layout, "article", &article.path)?;
+        }
+        let editions = load_editions(&config, &layouts, &sorted_articles)?;
         let partials = load_partials(&src_dir)?;
-        let pages = load_pages(&src_dir)?;
-        let codes = load_codes(&src_dir)?;
+        let pages = load_pages(&src_dir, &config, &highlighter)?;
+        for page in pages.iter() {
+            find_layout(&layouts, &page.layout, "page", &page.path)?;
+        }
+        let codes = load_codes(&src_dir, &highlighter, &sorted_articles)?;
         let resources = load_resources(&src_dir)?;
+        let mut generated_resources = Vec::new();
+        if let Some(css) = highlighter.stylesheet() {
+            generated_resources.push(GeneratedResource {
+                dest_path: PathBuf::from(highlight_config.stylesheet.trim_start_matches('/')),
+                content: css,
+            });
+        }
+        // The build goes on without the cache, for example in a read-only project directory.
+        if let Err(e) = highlighter.save_cache() {
+            log::warn!("Failed to save the highlight cache: {}", e);
+        }
 
         log::debug!("Finished to load project files.");
 
@@ -60,59 +105,78 @@ impl Blog {
             src_dir: src_dir,
             dest_dir: dest_dir,
 
-            articles_by_tag: articles_by_tag,
-            articles_by_year: articles_by_year,
+            articles_by_series: articles_by_series,
+            articles_by_author: articles_by_author,
             sorted_articles: sorted_articles,
+            editions: editions,
             layouts: layouts,
             partials: partials,
             pages: pages,
             codes: codes,
             resources: resources,
+            generated_resources: generated_resources,
         })
     }
 
     pub fn build(&self) -> Result<(), Error> {
         let mut renderer = self.init_renderer()?;
-        let tags = self.init_tags();
-        let years = self.init_years();
-        let recent_articles = self.init_recent_articles();
-        self.build_index_page(&renderer, &tags, &years, &recent_articles)?;
-        self.build_article_page(&mut renderer, &tags, &years, &recent_articles)?;
-        self.build_tag_page(&renderer, &tags, &years, &recent_articles)?;
-        self.build_year_page(&renderer, &tags, &years, &recent_articles)?;
-        self.build_general_page(&renderer)?;
-        self.build_rss(&renderer, &recent_articles)?;
+        let menu_pages = self.init_menu_pages();
+        for edition in self.editions.iter() {
+            let tags = self.init_tags(edition);
+            let years = self.init_years(edition);
+            let months = self.init_months(edition);
+            self.build_index_page(&renderer, edition, &tags, &years, &months, &menu_pages)?;
+            self.build_article_page(&mut renderer, edition, &tags, &years, &months, &menu_pages)?;
+            self.build_taxonomy_page(&renderer, edition, &tags, &years, &months, &menu_pages)?;
+            self.build_taxonomy_list_page(&renderer, edition, &tags, &years, &months, &menu_pages)?;
+            self.build_rss(&renderer, edition)?;
+        }
+        // Series, authors and general pages are shared by all languages.
+        let root = &self.editions[0];
+        let tags = self.init_tags(root);
+        let years = self.init_years(root);
+        let months = self.init_months(root);
+        self.build_series_page(&renderer, root, &tags, &years, &months, &menu_pages)?;
+        self.build_author_page(&renderer, root, &tags, &years, &months, &menu_pages)?;
+        self.build_general_page(&mut renderer, root, &tags, &years, &months, &menu_pages)?;
         self.put_resources()?;
+        self.put_bundle_files()?;
         Ok(())
     }
 
     fn build_index_page(
         &self,
         renderer: &Handlebars,
+        edition: &Edition,
         tags: &ViewItems,
         years: &ViewItems,
-        recent_articles: &[Rc<Article>],
+        months: &ViewItems,
+        menu_pages: &ViewItems,
     ) -> Result<(), Error> {
+        let recent_articles = self.init_recent_articles(edition);
         let template_string = match &self.layouts.index {
             Layout::Index(s) => s,
             _ => return Err(failure::format_err!("Invalid Layout variant.")),
         };
 
-        std::fs::create_dir_all(self.dest_dir.join("page"))?;
-
         let entries_per_page = match &self.config {
             Config::V1(ref c) => c.blog.index_page.entries_per_page,
         };
-        let paginator = Paginator::new(&self.sorted_articles, entries_per_page as usize);
+        let paginator = Paginator::new(&edition.sorted_articles, entries_per_page as usize);
         let num_pages = paginator.len();
+        let alternates = self.init_alternates(|e| Some(e.index_path(1)));
         for (mut i, page) in paginator.enumerate() {
             // The page number seen from users is 1 origin.
             i += 1;
 
             let mut data = Map::new();
             data.insert("articles".to_string(), handlebars::to_json(&page));
+            data.insert("lang".to_string(), handlebars::to_json(&edition.lang));
+            data.insert("alternates".to_string(), handlebars::to_json(&alternates));
             data.insert("tags".to_string(), handlebars::to_json(tags));
             data.insert("years".to_string(), handlebars::to_json(years));
+            data.insert("months".to_string(), handlebars::to_json(months));
+            data.insert("menu_pages".to_string(), handlebars::to_json(menu_pages));
             data.insert(
                 "recent_articles".to_string(),
                 handlebars::to_json(recent_articles),
@@ -123,32 +187,15 @@ impl Blog {
                 handlebars::to_json(self.site_root()),
             );
 
-            let mut paginate = Map::new();
-            paginate.insert("page_number".to_string(), serde_json::json!(i));
-            paginate.insert("num_pages".to_string(), serde_json::json!(num_pages));
-            if i > 1 {
-                paginate.insert(
-                    "prev_page".to_string(),
-                    serde_json::json!(format!("/page/{}/", i - 1)),
-                );
-            }
-            if i < num_pages {
-                paginate.insert(
-                    "next_page".to_string(),
-                    serde_json::json!(format!("/page/{}/", i + 1)),
-                );
-            }
+            let paginate = init_paginate(i, num_pages, |n| edition.index_path(n));
             data.insert("paginate".to_string(), handlebars::to_json(&paginate));
 
             let html = renderer.render_template(template_string.as_str(), &data)?;
-            let dest_path = if i == 1 {
-                self.dest_dir.join("index.html")
-            } else {
-                let dest_file_dir = self.dest_dir.join("page").join(&i.to_string());
-                std::fs::create_dir_all(&dest_file_dir)?;
-                dest_file_dir.join("index.html")
-            };
-            let mut file = File::create(dest_path)?;
+            let dest_file_dir = self
+                .dest_dir
+                .join(edition.index_path(i).trim_start_matches('/'));
+            std::fs::create_dir_all(&dest_file_dir)?;
+            let mut file = File::create(dest_file_dir.join("index.html"))?;
             file.write_all(html.as_bytes())?;
 
             log::debug!("Wrote index page {:?}.", file);
@@ -160,20 +207,43 @@ impl Blog {
     fn build_article_page(
         &self,
         renderer: &mut handlebars::Handlebars,
+        edition: &Edition,
         tags: &ViewItems,
         years: &ViewItems,
-        recent_articles: &[Rc<Article>],
+        months: &ViewItems,
+        menu_pages: &ViewItems,
     ) -> Result<(), Error> {
-        let template_string = match &self.layouts.article {
-            Layout::Article(s) => s,
-            _ => return Err(failure::format_err!("Invalid Layout variant.")),
-        };
-
-        for article in self.sorted_articles.iter() {
+        let recent_articles = self.init_recent_articles(edition);
+        for article in edition.sorted_articles.iter() {
+            if !edition.owns(article) {
+                continue;
+            }
+            let template_string =
+                find_layout(&self.layouts, &article.layout, "article", &article.path)?;
+            let translations = self.find_translations(article);
+            let alternates = self.init_alternates(|e| {
+                translations
+                    .iter()
+                    .find(|a| a.lang == e.lang)
+                    .map(|a| a.path.to_string_lossy().to_string())
+            });
             let mut data = Map::new();
             data.insert("article".to_string(), handlebars::to_json(&article));
+            data.insert("lang".to_string(), handlebars::to_json(&edition.lang));
+            data.insert(
+                "translations".to_string(),
+                handlebars::to_json(
+                    translations
+                        .iter()
+                        .filter(|a| !Rc::ptr_eq(a, article))
+                        .collect::<Vec<_>>(),
+                ),
+            );
+            data.insert("alternates".to_string(), handlebars::to_json(&alternates));
             data.insert("tags".to_string(), handlebars::to_json(tags));
             data.insert("years".to_string(), handlebars::to_json(years));
+            data.insert("months".to_string(), handlebars::to_json(months));
+            data.insert("menu_pages".to_string(), handlebars::to_json(menu_pages));
             data.insert(
                 "recent_articles".to_string(),
                 handlebars::to_json(recent_articles),
@@ -184,9 +254,73 @@ impl Blog {
                 handlebars::to_json(self.site_root()),
             );
 
+            let (prev_article, next_article) = find_neighbors(&edition.sorted_articles, article);
+            data.insert(
+                "prev_article".to_string(),
+                handlebars::to_json(prev_article.map(|a| a.link())),
+            );
+            data.insert(
+                "next_article".to_string(),
+                handlebars::to_json(next_article.map(|a| a.link())),
+            );
+            let mut tag_neighbors = Vec::new();
+            for tag in article.tags.iter() {
+                let (prev_article, next_article) =
+                    find_neighbors(&edition.articles_by_tag[&tag.slug], article);
+                let mut m = Map::new();
+                m.insert("tag".to_string(), serde_json::json!(tag));
+                m.insert(
+                    "prev_article".to_string(),
+                    handlebars::to_json(prev_article.map(|a| a.link())),
+                );
+                m.insert(
+                    "next_article".to_string(),
+                    handlebars::to_json(next_article.map(|a| a.link())),
+                );
+                tag_neighbors.push(m);
+            }
+            data.insert(
+                "tag_neighbors".to_string(),
+                handlebars::to_json(&tag_neighbors),
+            );
+            data.insert(
+                "related_articles".to_string(),
+                handlebars::to_json(find_related_articles(
+                    &edition.articles_by_tag,
+                    article,
+                    self.num_related_articles(),
+                )),
+            );
+            if let Some(ref series_name) = article.series {
+                let articles = &self.articles_by_series[series_name];
+                let mut series = Map::new();
+                let slug = series_slug(series_name);
+                series.insert("name".to_string(), serde_json::json!(series_name));
+                series.insert("slug".to_string(), serde_json::json!(slug));
+                series.insert("path".to_string(), serde_json::json!(series_path(&slug)));
+                series.insert("articles".to_string(), handlebars::to_json(articles));
+                // The position seen from users is 1 origin.
+                series.insert(
+                    "position".to_string(),
+                    serde_json::json!(
+                        articles
+                            .iter()
+                            .position(|a| Rc::ptr_eq(a, article))
+                            .unwrap()
+                            + 1
+                    ),
+                );
+                series.insert("len".to_string(), serde_json::json!(articles.len()));
+                data.insert("series".to_string(), handlebars::to_json(&series));
+            }
+
             renderer.register_partial("article_html", &article.html)?;
             let html = renderer.render_template(template_string.as_str(), &data)?;
-            let dest_full_path = self.dest_dir.join(&article.path.strip_prefix("/")?);
+            let mut dest_full_path = self.dest_dir.join(&article.path.strip_prefix("/")?);
+            // A directory style path like `/2019/06/23/example/` is served as its `index.html`.
+            if article.path.to_string_lossy().ends_with('/') {
+                dest_full_path = dest_full_path.join("index.html");
+            }
             std::fs::create_dir_all(self.extract_parent_dir(&dest_full_path)?)?;
             let mut file = File::create(dest_full_path)?;
             file.write_all(html.as_bytes())?;
@@ -197,23 +331,130 @@ impl Blog {
         Ok(())
     }
 
-    fn build_tag_page(
+    fn build_taxonomy_page(
         &self,
         renderer: &Handlebars,
+        edition: &Edition,
         tags: &ViewItems,
         years: &ViewItems,
-        recent_articles: &[Rc<Article>],
+        months: &ViewItems,
+        menu_pages: &ViewItems,
     ) -> Result<(), Error> {
-        let template_string = match &self.layouts.tag {
-            Layout::Tag(s) => s,
-            _ => return Err(failure::format_err!("Invalid Layout variant.")),
-        };
+        let recent_articles = self.init_recent_articles(edition);
+        for taxonomy in edition.taxonomies.iter() {
+            if taxonomy.terms.is_empty() {
+                continue;
+            }
+            let template_string = self.layouts.find(&taxonomy.layout).ok_or_else(|| {
+                failure::format_err!(
+                    "Put layouts/{}.hbs to build pages of {}.",
+                    taxonomy.layout,
+                    taxonomy.name
+                )
+            })?;
+
+            for term in taxonomy.terms.iter() {
+                let alternates = self.init_alternates(|e| {
+                    let t = e.taxonomies.iter().find(|t| t.name == taxonomy.name)?;
+                    let u = t.terms.iter().find(|u| u.slug == term.slug)?;
+                    Some(t.term_path(u, 1))
+                });
+                let mut data = Map::new();
+                data.insert("lang".to_string(), handlebars::to_json(&edition.lang));
+                data.insert("alternates".to_string(), handlebars::to_json(&alternates));
+                data.insert("tags".to_string(), handlebars::to_json(tags));
+                data.insert("years".to_string(), handlebars::to_json(years));
+                data.insert("months".to_string(), handlebars::to_json(months));
+                data.insert("menu_pages".to_string(), handlebars::to_json(menu_pages));
+                data.insert("taxonomy".to_string(), handlebars::to_json(&taxonomy.name));
+                data.insert(
+                    "term".to_string(),
+                    handlebars::to_json(taxonomy.term_view(term)),
+                );
+                if let Some(term_variable) = taxonomy.term_variable {
+                    data.insert(term_variable.to_string(), handlebars::to_json(&term.name));
+                }
+                data.insert(
+                    "recent_articles".to_string(),
+                    handlebars::to_json(recent_articles),
+                );
+                data.insert(
+                    "site_root".to_string(),
+                    handlebars::to_json(self.site_root()),
+                );
+
+                let paginator = Paginator::new(&term.articles, taxonomy.entries_per_page as usize);
+                let num_pages = paginator.len();
+                for (mut i, page) in paginator.enumerate() {
+                    // The page number seen from users is 1 origin.
+                    i += 1;
+
+                    data.insert("articles".to_string(), handlebars::to_json(page));
+
+                    let paginate = init_paginate(i, num_pages, |n| taxonomy.term_path(term, n));
+                    data.insert("paginate".to_string(), handlebars::to_json(&paginate));
+
+                    let html = renderer.render_template(template_string.as_str(), &data)?;
+                    let dest_full_path = self
+                        .dest_dir
+                        .join(taxonomy.term_path(term, i).trim_start_matches('/'));
+                    std::fs::create_dir_all(self.extract_parent_dir(&dest_full_path)?)?;
+                    let mut file = File::create(dest_full_path)?;
+                    file.write_all(html.as_bytes())?;
+
+                    log::debug!("Wrote {} page {:?}", taxonomy.name, file);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn build_taxonomy_list_page(
+        &self,
+        renderer: &Handlebars,
+        edition: &Edition,
+        tags: &ViewItems,
+        years: &ViewItems,
+        months: &ViewItems,
+        menu_pages: &ViewItems,
+    ) -> Result<(), Error> {
+        let recent_articles = self.init_recent_articles(edition);
+        for taxonomy in edition.taxonomies.iter() {
+            let list_layout = match &taxonomy.list_layout {
+                Some(list_layout) => list_layout,
+                None => continue,
+            };
+            let template_string = self.layouts.find(list_layout).ok_or_else(|| {
+                failure::format_err!(
+                    "Put layouts/{}.hbs to build the list page of {}.",
+                    list_layout,
+                    taxonomy.name
+                )
+            })?;
+
+            let terms: ViewItems = taxonomy
+                .terms
+                .iter()
+                .map(|term| taxonomy.term_view(term))
+                .collect();
+
+            let alternates = self.init_alternates(|e| {
+                e.taxonomies
+                    .iter()
+                    .find(|t| t.name == taxonomy.name && t.list_layout.is_some())
+                    .map(|t| t.list_path())
+            });
 
-        for (tag, articles) in self.articles_by_tag.iter() {
             let mut data = Map::new();
+            data.insert("lang".to_string(), handlebars::to_json(&edition.lang));
+            data.insert("alternates".to_string(), handlebars::to_json(&alternates));
             data.insert("tags".to_string(), handlebars::to_json(tags));
             data.insert("years".to_string(), handlebars::to_json(years));
-            data.insert("tag_name".to_string(), handlebars::to_json(&tag));
+            data.insert("months".to_string(), handlebars::to_json(months));
+            data.insert("menu_pages".to_string(), handlebars::to_json(menu_pages));
+            data.insert("taxonomy".to_string(), handlebars::to_json(&taxonomy.name));
+            data.insert("terms".to_string(), handlebars::to_json(&terms));
             data.insert(
                 "recent_articles".to_string(),
                 handlebars::to_json(recent_articles),
@@ -223,80 +464,114 @@ impl Blog {
                 handlebars::to_json(self.site_root()),
             );
 
-            let entries_per_page = match &self.config {
-                Config::V1(ref c) => c.blog.tag_page.entries_per_page,
-            };
-            let paginator = Paginator::new(&articles, entries_per_page as usize);
-            let num_pages = paginator.len();
-            for (mut i, page) in paginator.enumerate() {
-                // The page number seen from users is 1 origin.
-                i += 1;
-
-                data.insert("articles".to_string(), handlebars::to_json(page));
+            let html = renderer.render_template(template_string.as_str(), &data)?;
+            let dest_full_path = self
+                .dest_dir
+                .join(taxonomy.list_path().trim_start_matches('/'));
+            std::fs::create_dir_all(self.extract_parent_dir(&dest_full_path)?)?;
+            let mut file = File::create(dest_full_path)?;
+            file.write_all(html.as_bytes())?;
 
-                let mut paginate = Map::new();
-                paginate.insert("page_number".to_string(), serde_json::json!(i));
-                paginate.insert("num_pages".to_string(), serde_json::json!(num_pages));
-                if i > 1 {
-                    if i == 2 {
-                        paginate.insert(
-                            "prev_page".to_string(),
-                            serde_json::json!(format!("/tags/{}.html", tag)),
-                        );
-                    } else {
-                        paginate.insert(
-                            "prev_page".to_string(),
-                            serde_json::json!(format!("/tags/{}/page/{}.html", tag, i - 1)),
-                        );
-                    }
-                }
-                if i < num_pages {
-                    paginate.insert(
-                        "next_page".to_string(),
-                        serde_json::json!(format!("/tags/{}/page/{}.html", tag, i + 1)),
-                    );
-                }
-                data.insert("paginate".to_string(), handlebars::to_json(&paginate));
+            log::debug!("Wrote list page of {} {:?}", taxonomy.name, file);
+        }
 
-                let html = renderer.render_template(template_string.as_str(), &data)?;
-                let dest_full_path = if i == 1 {
-                    self.dest_dir.join("tags").join(&tag).with_extension("html")
-                } else {
-                    self.dest_dir
-                        .join("tags")
-                        .join(&tag)
-                        .join("page")
-                        .join(&i.to_string())
-                        .with_extension("html")
-                };
-                std::fs::create_dir_all(self.extract_parent_dir(&dest_full_path)?)?;
-                let mut file = File::create(dest_full_path)?;
-                file.write_all(html.as_bytes())?;
+        Ok(())
+    }
 
-                log::debug!("Wrote tag page {:?}", file);
+    fn build_series_page(
+        &self,
+        renderer: &Handlebars,
+        edition: &Edition,
+        tags: &ViewItems,
+        years: &ViewItems,
+        months: &ViewItems,
+        menu_pages: &ViewItems,
+    ) -> Result<(), Error> {
+        let recent_articles = self.init_recent_articles(edition);
+        if self.articles_by_series.is_empty() {
+            return Ok(());
+        }
+        let template_string = match &self.layouts.series {
+            Some(Layout::Series(s)) => s,
+            Some(_) => return Err(failure::format_err!("Invalid Layout variant.")),
+            None => {
+                return Err(failure::format_err!(
+                    "Put layouts/series.hbs to build pages of series."
+                ))
             }
+        };
+
+        for (series, articles) in self.articles_by_series.iter() {
+            let mut data = Map::new();
+            data.insert("tags".to_string(), handlebars::to_json(tags));
+            data.insert("years".to_string(), handlebars::to_json(years));
+            data.insert("months".to_string(), handlebars::to_json(months));
+            data.insert("menu_pages".to_string(), handlebars::to_json(menu_pages));
+            let slug = series_slug(series);
+            data.insert("series_name".to_string(), handlebars::to_json(series));
+            data.insert("series_slug".to_string(), handlebars::to_json(&slug));
+            data.insert(
+                "series_path".to_string(),
+                handlebars::to_json(series_path(&slug)),
+            );
+            data.insert("articles".to_string(), handlebars::to_json(articles));
+            data.insert(
+                "recent_articles".to_string(),
+                handlebars::to_json(recent_articles),
+            );
+            data.insert(
+                "site_root".to_string(),
+                handlebars::to_json(self.site_root()),
+            );
+
+            let html = renderer.render_template(template_string.as_str(), &data)?;
+            let dest_full_path = self
+                .dest_dir
+                .join(series_path(&slug).trim_start_matches('/'));
+            std::fs::create_dir_all(self.extract_parent_dir(&dest_full_path)?)?;
+            let mut file = File::create(dest_full_path)?;
+            file.write_all(html.as_bytes())?;
+
+            log::debug!("Wrote series page {:?}", file);
         }
 
         Ok(())
     }
 
-    fn build_year_page(
+    fn build_author_page(
         &self,
         renderer: &Handlebars,
+        edition: &Edition,
         tags: &ViewItems,
         years: &ViewItems,
-        recent_articles: &[Rc<Article>],
+        months: &ViewItems,
+        menu_pages: &ViewItems,
     ) -> Result<(), Error> {
-        let template_string = match &self.layouts.year {
-            Layout::Year(s) => s,
-            _ => return Err(failure::format_err!("Invalid Layout variant.")),
+        let recent_articles = self.init_recent_articles(edition);
+        if self.articles_by_author.is_empty() {
+            return Ok(());
+        }
+        let template_string = match &self.layouts.author {
+            Some(Layout::Author(s)) => s,
+            Some(_) => return Err(failure::format_err!("Invalid Layout variant.")),
+            None => {
+                return Err(failure::format_err!(
+                    "Put layouts/author.hbs to build pages of authors."
+                ))
+            }
+        };
+        let (authors, entries_per_page) = match &self.config {
+            Config::V1(ref c) => (&c.blog.authors, c.blog.author_page.entries_per_page),
         };
 
-        for (year, articles) in self.articles_by_year.iter() {
+        for (key, articles) in self.articles_by_author.iter() {
+            let author = ArticleAuthor::new(key, &authors[key]);
             let mut data = Map::new();
             data.insert("tags".to_string(), handlebars::to_json(tags));
             data.insert("years".to_string(), handlebars::to_json(years));
-            data.insert("year_num".to_string(), handlebars::to_json(year));
+            data.insert("months".to_string(), handlebars::to_json(months));
+            data.insert("menu_pages".to_string(), handlebars::to_json(menu_pages));
+            data.insert("author".to_string(), handlebars::to_json(&author));
             data.insert(
                 "recent_articles".to_string(),
                 handlebars::to_json(recent_articles),
@@ -306,10 +581,7 @@ impl Blog {
                 handlebars::to_json(self.site_root()),
             );
 
-            let entries_per_page = match &self.config {
-                Config::V1(ref c) => c.blog.year_page.entries_per_page,
-            };
-            let paginator = Paginator::new(&articles, entries_per_page as usize);
+            let paginator = Paginator::new(articles, entries_per_page as usize);
             let num_pages = paginator.len();
             for (mut i, page) in paginator.enumerate() {
                 // The page number seen from users is 1 origin.
@@ -317,64 +589,54 @@ impl Blog {
 
                 data.insert("articles".to_string(), handlebars::to_json(page));
 
-                let mut paginate = Map::new();
-                paginate.insert("page_number".to_string(), serde_json::json!(i));
-                paginate.insert("num_pages".to_string(), serde_json::json!(num_pages));
-                if i > 1 {
-                    if i == 2 {
-                        paginate.insert(
-                            "prev_page".to_string(),
-                            serde_json::json!(format!("/{}.html", year)),
-                        );
-                    } else {
-                        paginate.insert(
-                            "prev_page".to_string(),
-                            serde_json::json!(format!("/{}/page/{}.html", year, i - 1)),
-                        );
-                    }
-                }
-                if i < num_pages {
-                    paginate.insert(
-                        "next_page".to_string(),
-                        serde_json::json!(format!("/{}/page/{}.html", year, i + 1)),
-                    );
-                }
+                let paginate = init_paginate(i, num_pages, |n| author_path(&author.slug, n));
                 data.insert("paginate".to_string(), handlebars::to_json(&paginate));
 
                 let html = renderer.render_template(template_string.as_str(), &data)?;
-                let dest_full_path = if i == 1 {
-                    self.dest_dir.join(&year.to_string()).with_extension("html")
-                } else {
-                    self.dest_dir
-                        .join(&year.to_string())
-                        .join("page")
-                        .join(&i.to_string())
-                        .with_extension("html")
-                };
+                let dest_full_path = self
+                    .dest_dir
+                    .join(author_path(&author.slug, i).trim_start_matches('/'));
                 std::fs::create_dir_all(self.extract_parent_dir(&dest_full_path)?)?;
                 let mut file = File::create(dest_full_path)?;
                 file.write_all(html.as_bytes())?;
 
-                log::debug!("Wrote year page {:?}", file);
+                log::debug!("Wrote author page {:?}", file);
             }
         }
+
         Ok(())
     }
 
-    fn build_general_page(&self, renderer: &Handlebars) -> Result<(), Error> {
-        let template_string = match &self.layouts.page {
-            Layout::Page(s) => s,
-            _ => return Err(failure::format_err!("Invalid Layout variant.")),
-        };
-
+    fn build_general_page(
+        &self,
+        renderer: &mut Handlebars,
+        edition: &Edition,
+        tags: &ViewItems,
+        years: &ViewItems,
+        months: &ViewItems,
+        menu_pages: &ViewItems,
+    ) -> Result<(), Error> {
+        let recent_articles = self.init_recent_articles(edition);
         for page in self.pages.iter() {
+            let template_string = find_layout(&self.layouts, &page.layout, "page", &page.path)?;
+
             let mut data = Map::new();
             data.insert("page".to_string(), handlebars::to_json(page));
+            data.insert("tags".to_string(), handlebars::to_json(tags));
+            data.insert("years".to_string(), handlebars::to_json(years));
+            data.insert("months".to_string(), handlebars::to_json(months));
+            data.insert("menu_pages".to_string(), handlebars::to_json(menu_pages));
+            data.insert(
+                "recent_articles".to_string(),
+                handlebars::to_json(recent_articles),
+            );
+            data.insert("codes".to_string(), handlebars::to_json(&self.codes));
             data.insert(
                 "site_root".to_string(),
                 handlebars::to_json(self.site_root()),
             );
 
+            renderer.register_partial("page_html", &page.html)?;
             let html = renderer.render_template(template_string.as_str(), &data)?;
             let dest_full_path = self.dest_dir.join(&page.path).with_extension("html");
             std::fs::create_dir_all(self.extract_parent_dir(&dest_full_path)?)?;
@@ -387,11 +649,8 @@ impl Blog {
         Ok(())
     }
 
-    fn build_rss(
-        &self,
-        renderer: &Handlebars,
-        recent_articles: &[Rc<Article>],
-    ) -> Result<(), Error> {
+    fn build_rss(&self, renderer: &Handlebars, edition: &Edition) -> Result<(), Error> {
+        let recent_articles = self.init_recent_articles(edition);
         let template_string = match &self.layouts.rss {
             Layout::Rss(s) => s,
             _ => return Err(failure::format_err!("Invalid Layout variant.")),
@@ -399,16 +658,32 @@ impl Blog {
 
         let mut data = Map::new();
         data.insert("articles".to_string(), handlebars::to_json(recent_articles));
+        data.insert("lang".to_string(), handlebars::to_json(&edition.lang));
+        data.insert(
+            "feed_path".to_string(),
+            handlebars::to_json(edition.feed_path()),
+        );
+        data.insert("codes".to_string(), handlebars::to_json(&self.codes));
         data.insert(
             "site_root".to_string(),
             handlebars::to_json(self.site_root()),
         );
+        // A feed without articles falls back to the build time, because `<updated>` is required.
+        let updated = match recent_articles.iter().map(|a| a.last_modified()).max() {
+            Some(updated) => updated,
+            None => chrono::Utc::now().with_timezone(&self.timezone_offset()?),
+        };
+        data.insert("updated".to_string(), handlebars::to_json(updated));
 
         let html = renderer.render_template(&template_string, &data)?;
-        let mut file = File::create(self.dest_dir.join("feed.xml"))?;
+        let dest_full_path = self
+            .dest_dir
+            .join(edition.feed_path().trim_start_matches('/'));
+        std::fs::create_dir_all(self.extract_parent_dir(&dest_full_path)?)?;
+        let mut file = File::create(dest_full_path)?;
         file.write_all(html.as_bytes())?;
 
-        log::debug!("Wrote RSS");
+        log::debug!("Wrote RSS {:?}", file);
 
         Ok(())
     }
@@ -431,6 +706,33 @@ impl Blog {
                 }
             }
         }
+        for r in self.generated_resources.iter() {
+            let dest_full_path = self.dest_dir.join(&r.dest_path);
+            std::fs::create_dir_all(self.extract_parent_dir(&dest_full_path)?)?;
+            let mut file = File::create(dest_full_path)?;
+            file.write_all(r.content.as_bytes())?;
+            log::debug!("Wrote generated file {:?}", file);
+        }
+
+        Ok(())
+    }
+
+    fn put_bundle_files(&self) -> Result<(), Error> {
+        for article in self.sorted_articles.iter() {
+            let bundle_dir = match &article.bundle_dir {
+                Some(d) => d,
+                None => continue,
+            };
+            for src_path in article.bundle_files()? {
+                let dest_full_path = self
+                    .dest_dir
+                    .join(article.output_dir().trim_start_matches('/'))
+                    .join(src_path.strip_prefix(bundle_dir)?);
+                std::fs::create_dir_all(self.extract_parent_dir(&dest_full_path)?)?;
+                std::fs::copy(&src_path, &dest_full_path)?;
+                log::debug!("Copied bundle file {:?}", dest_full_path);
+            }
+        }
 
         Ok(())
     }
@@ -452,36 +754,40 @@ impl Blog {
             Box::new(view_helper::article_ogp_meta_tags),
         );
         renderer.register_helper("embed_code", Box::new(view_helper::embed_code));
+        let excerpt = match &self.config {
+            Config::V1(ref c) => &c.blog.excerpt,
+        };
         renderer.register_helper(
             "summarize_article",
-            Box::new(view_helper::summarize_article),
+            Box::new(view_helper::SummarizeArticle {
+                elements: excerpt.elements as usize,
+                characters: excerpt.characters.map(|c| c as usize),
+            }),
         );
         renderer.register_helper("time_now", Box::new(view_helper::time_now));
 
         Ok(renderer)
     }
 
-    fn init_tags(&self) -> ViewItems {
-        let mut tag_keys: Vec<_> = self.articles_by_tag.keys().collect();
-        tag_keys.sort();
-        let mut tags = Vec::new();
-        for tag_key in tag_keys {
-            let mut m = Map::new();
-            m.insert("key".to_string(), serde_json::json!(tag_key));
-            m.insert(
-                "len".to_string(),
-                serde_json::json!(self.articles_by_tag.get(tag_key).unwrap().len()),
-            );
-            tags.push(m);
-        }
-        tags
+    fn init_tags(&self, edition: &Edition) -> ViewItems {
+        // Tags are always the first taxonomy.
+        let taxonomy = &edition.taxonomies[0];
+        taxonomy
+            .terms
+            .iter()
+            .map(|term| taxonomy.term_view(term))
+            .collect()
     }
 
-    fn init_years(&self) -> ViewItems {
+    fn init_years(&self, edition: &Edition) -> ViewItems {
         let mut years = Vec::new();
-        for (year, articles) in &self.articles_by_year {
+        for (year, articles) in &edition.articles_by_year {
             let mut m = Map::new();
             m.insert("year".to_string(), serde_json::json!(year));
+            m.insert(
+                "path".to_string(),
+                serde_json::json!(format!("{}/{}.html", edition.prefix, year)),
+            );
             m.insert("len".to_string(), serde_json::json!(articles.len()));
             years.push(m);
         }
@@ -495,12 +801,86 @@ impl Blog {
         years
     }
 
-    fn init_recent_articles(&self) -> &[Rc<Article>] {
-        let sorted_article_length = self.sorted_articles.len();
+    // Months link to monthly archives, so they are empty when the archives are not built without
+    // `layouts/month.hbs`.
+    fn init_months(&self, edition: &Edition) -> ViewItems {
+        if !edition.taxonomies.iter().any(|t| t.name == "months") {
+            return Vec::new();
+        }
+        let mut months: Vec<_> = edition.articles_by_month.iter().collect();
+        months.sort_by(|(m1, _), (m2, _)| m2.cmp(m1));
+        let mut month_items = Vec::new();
+        for ((year, month), articles) in months {
+            let mut m = Map::new();
+            m.insert("year".to_string(), serde_json::json!(year));
+            m.insert("month".to_string(), serde_json::json!(month));
+            m.insert(
+                "path".to_string(),
+                serde_json::json!(format!("{}/{}/{:02}.html", edition.prefix, year, month)),
+            );
+            m.insert("len".to_string(), serde_json::json!(articles.len()));
+            month_items.push(m);
+        }
+        month_items
+    }
+
+    // Pages which have `menu` in their front matter, sorted by `menu_order` and then by path.
+    fn init_menu_pages(&self) -> ViewItems {
+        let mut pages: Vec<_> = self.pages.iter().filter(|p| p.menu.is_some()).collect();
+        pages.sort_by_key(|p| (p.menu_order.is_none(), p.menu_order, &p.path));
+        let mut menu_pages = Vec::new();
+        for page in pages {
+            let mut m = Map::new();
+            m.insert("name".to_string(), serde_json::json!(page.menu));
+            m.insert(
+                "path".to_string(),
+                serde_json::json!(format!(
+                    "/{}",
+                    page.path.with_extension("html").to_string_lossy()
+                )),
+            );
+            menu_pages.push(m);
+        }
+        menu_pages
+    }
+
+    fn init_recent_articles<'a>(&self, edition: &'a Edition) -> &'a [Rc<Article>] {
+        let sorted_article_length = edition.sorted_articles.len();
         if sorted_article_length < 5 {
-            &self.sorted_articles[0..sorted_article_length]
+            &edition.sorted_articles[0..sorted_article_length]
         } else {
-            &self.sorted_articles[0..5]
+            &edition.sorted_articles[0..5]
+        }
+    }
+
+    // The same page in each language for `hreflang` alternates. `path_of` returns the path of
+    // the page in an edition if it exists.
+    fn init_alternates<F>(&self, path_of: F) -> ViewItems
+    where
+        F: Fn(&Edition) -> Option<String>,
+    {
+        let mut alternates = Vec::new();
+        for edition in self.editions.iter().filter(|e| e.lang.is_some()) {
+            if let Some(path) = path_of(edition) {
+                let mut m = Map::new();
+                m.insert("lang".to_string(), serde_json::json!(edition.lang));
+                m.insert("name".to_string(), serde_json::json!(edition.name));
+                m.insert("path".to_string(), serde_json::json!(path));
+                alternates.push(m);
+            }
+        }
+        alternates
+    }
+
+    // Articles sharing `translation_key` with `article`, including itself.
+    fn find_translations(&self, article: &Article) -> Vec<&Rc<Article>> {
+        match article.translation_key {
+            Some(ref key) => self
+                .sorted_articles
+                .iter()
+                .filter(|a| a.translation_key.as_ref() == Some(key))
+                .collect(),
+            None => Vec::new(),
         }
     }
 
@@ -511,9 +891,221 @@ impl Blog {
             .to_path_buf())
     }
 
+    fn num_related_articles(&self) -> usize {
+        match self.config {
+            Config::V1(ref c) => c.blog.article_page.related_articles as usize,
+        }
+    }
+
+    fn timezone_offset(&self) -> Result<chrono::FixedOffset, Error> {
+        match self.config {
+            Config::V1(ref c) => c.blog.timezone_offset(),
+        }
+    }
+
     fn site_root(&self) -> &str {
         match self.config {
             Config::V1(ref c) => c.blog.site_root.as_str(),
         }
     }
 }
+
+// Links between pages of a paginated list passed to layouts as `paginate`. `path_of` returns
+// the URL of a page from its number.
+fn init_paginate<F>(
+    page_number: usize,
+    num_pages: usize,
+    path_of: F,
+) -> Map<String, handlebars::JsonValue>
+where
+    F: Fn(usize) -> String,
+{
+    let mut paginate = Map::new();
+    paginate.insert("page_number".to_string(), serde_json::json!(page_number));
+    paginate.insert("num_pages".to_string(), serde_json::json!(num_pages));
+    if page_number > 1 {
+        paginate.insert(
+            "prev_page".to_string(),
+            serde_json::json!(path_of(page_number - 1)),
+        );
+    }
+    if page_number < num_pages {
+        paginate.insert(
+            "next_page".to_string(),
+            serde_json::json!(path_of(page_number + 1)),
+        );
+    }
+    paginate
+}
+
+// Find the layout chosen by `layout` in front matter of an article or a page at `path`.
+fn find_layout<'a>(
+    layouts: &'a Layouts,
+    layout: &Option<String>,
+    default: &str,
+    path: &PathBuf,
+) -> Result<&'a String, Error> {
+    let layout = layout.as_ref().map_or(default, |l| l.as_str());
+    layouts.find(layout).ok_or_else(|| {
+        failure::format_err!(
+            "Layout `{}` of {:?} is not found. Put layouts/{}.hbs.",
+            layout,
+            path,
+            layout
+        )
+    })
+}
+
+// Find the previous (older) and the next (newer) article of `article` in `articles` sorted by
+// date in descending order.
+fn find_neighbors<'a>(
+    articles: &'a [Rc<Article>],
+    article: &Rc<Article>,
+) -> (Option<&'a Rc<Article>>, Option<&'a Rc<Article>>) {
+    match articles.iter().position(|a| Rc::ptr_eq(a, article)) {
+        Some(i) => (
+            articles.get(i + 1),
+            if i > 0 { articles.get(i - 1) } else { None },
+        ),
+        None => (None, None),
+    }
+}
+
+// Rank articles by the number of shared tags, then by date, and then by path to keep the order
+// stable.
+fn find_related_articles<'a>(
+    articles_by_tag: &'a ArticlesByTag,
+    article: &Rc<Article>,
+    limit: usize,
+) -> Vec<&'a Rc<Article>> {
+    let mut shared_tag_counts: HashMap<&PathBuf, (usize, &Rc<Article>)> = HashMap::new();
+    for tag in article.tags.iter() {
+        for a in articles_by_tag[&tag.slug].iter() {
+            if !Rc::ptr_eq(a, article) {
+                shared_tag_counts.entry(&a.path).or_insert((0, a)).0 += 1;
+            }
+        }
+    }
+
+    let mut related_articles: Vec<_> = shared_tag_counts.values().collect();
+    related_articles.sort_by(|(c1, a1), (c2, a2)| {
+        c2.cmp(c1)
+            .then_with(|| a2.datetime.cmp(&a1.datetime))
+            .then_with(|| a1.path.cmp(&a2.path))
+    });
+    related_articles
+        .into_iter()
+        .take(limit)
+        .map(|(_, a)| *a)
+        .collect()
+}
+
+#[test]
+fn test_find_neighbors() {
+    let articles = vec![
+        test_article("/c.html", "2019-06-23T00:00:00+09:00", &["rust"]),
+        test_article("/b.html", "2019-06-22T00:00:00+09:00", &[]),
+        test_article("/a.html", "2019-06-21T00:00:00+09:00", &["rust"]),
+    ];
+    let paths = |(prev, next): (Option<&Rc<Article>>, Option<&Rc<Article>>)| {
+        (prev.map(|a| a.path.clone()), next.map(|a| a.path.clone()))
+    };
+
+    // The newest article has no next one, and the oldest has no previous one.
+    assert_eq!(
+        (Some(PathBuf::from("/b.html")), None),
+        paths(find_neighbors(&articles, &articles[0]))
+    );
+    assert_eq!(
+        (
+            Some(PathBuf::from("/a.html")),
+            Some(PathBuf::from("/c.html"))
+        ),
+        paths(find_neighbors(&articles, &articles[1]))
+    );
+    assert_eq!(
+        (None, Some(PathBuf::from("/b.html"))),
+        paths(find_neighbors(&articles, &articles[2]))
+    );
+
+    // Articles of a tag skip others, and an article out of the list such as a skipped draft has
+    // no neighbors.
+    let rust_articles = vec![articles[0].clone(), articles[2].clone()];
+    assert_eq!(
+        (Some(PathBuf::from("/a.html")), None),
+        paths(find_neighbors(&rust_articles, &articles[0]))
+    );
+    assert_eq!(
+        (None, None),
+        paths(find_neighbors(&rust_articles, &articles[1]))
+    );
+
+    let link = serde_json::to_value(articles[0].link()).unwrap();
+    assert_eq!(
+        serde_json::json!({"title": "/c.html", "path": "/c.html", "date": "2019-06-23"}),
+        link
+    );
+}
+
+#[test]
+fn test_find_related_articles() {
+    let article = test_article("/x.html", "2019-06-20T00:00:00+09:00", &["rust", "blog"]);
+    let articles = [
+        test_article("/e.html", "2019-06-25T00:00:00+09:00", &["blog"]),
+        test_article("/d.html", "2019-06-24T00:00:00+09:00", &["other"]),
+        test_article("/c.html", "2019-06-23T00:00:00+09:00", &["rust"]),
+        test_article("/b.html", "2019-06-22T00:00:00+09:00", &["rust"]),
+        test_article("/a.html", "2019-06-22T00:00:00+09:00", &["rust"]),
+        test_article("/f.html", "2019-06-21T00:00:00+09:00", &["rust", "blog"]),
+        article.clone(),
+    ];
+    let mut articles_by_tag = ArticlesByTag::new();
+    for a in articles.iter() {
+        for tag in a.tags.iter() {
+            articles_by_tag
+                .entry(tag.slug.clone())
+                .or_default()
+                .push(a.clone());
+        }
+    }
+    let paths = |related: Vec<&Rc<Article>>| -> Vec<String> {
+        related
+            .iter()
+            .map(|a| a.path.to_string_lossy().into_owned())
+            .collect()
+    };
+
+    // More shared tags rank first even if older, the same number of them is ordered by date,
+    // and the same date by path. The article itself and ones without shared tags are excluded.
+    assert_eq!(
+        vec!["/f.html", "/e.html", "/c.html", "/a.html", "/b.html"],
+        paths(find_related_articles(&articles_by_tag, &article, 10))
+    );
+    assert_eq!(
+        vec!["/f.html", "/e.html"],
+        paths(find_related_articles(&articles_by_tag, &article, 2))
+    );
+    assert!(find_related_articles(&articles_by_tag, &article, 0).is_empty());
+}
+
+#[test]
+fn test_init_paginate() {
+    let path_of = |n| crate::article::author_path("jane-doe", n);
+    assert_eq!(
+        serde_json::json!({"page_number": 1, "num_pages": 3, "next_page": "/authors/jane-doe/page/2.html"}),
+        serde_json::Value::Object(init_paginate(1, 3, path_of))
+    );
+    assert_eq!(
+        serde_json::json!({
+            "page_number": 2,
+            "num_pages": 3,
+            "prev_page": "/authors/jane-doe.html",
+            "next_page": "/authors/jane-doe/page/3.html",
+        }),
+        serde_json::Value::Object(init_paginate(2, 3, path_of))
+    );
+    assert_eq!(
+        serde_json::json!({"page_number": 1, "num_pages": 1}),
+        serde_json::Value::Object(init_paginate(1, 1, path_of))
+    );
+}
@@ -1,10 +1,13 @@
-use crate::converter;
+use crate::article::{resolve_relative_path, Article};
+use crate::converter::{self, Highlighter};
 use failure::Error;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::prelude::*;
 use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::Arc;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Code {
@@ -13,8 +16,13 @@ pub struct Code {
     highlighted_html: String,
 }
 
-pub fn load_codes(src_dir: &PathBuf) -> Result<HashMap<PathBuf, Code>, Error> {
-    let mut codes = HashMap::new();
+pub fn load_codes(
+    src_dir: &PathBuf,
+    highlighter: &Arc<Highlighter>,
+    articles: &[Rc<Article>],
+) -> Result<HashMap<PathBuf, Code>, Error> {
+    // Pairs of a key path and a file path, which are distributed to threads.
+    let mut targets = Vec::new();
 
     let code_dir_glob = glob::glob(&src_dir.join("codes/**/*").to_str().unwrap())?;
 
@@ -22,30 +30,84 @@ pub fn load_codes(src_dir: &PathBuf) -> Result<HashMap<PathBuf, Code>, Error> {
         match entry {
             Ok(path) => {
                 if std::fs::metadata(&path)?.is_file() {
-                    let code = load_code(&path)?;
                     let key_path = PathBuf::from("/")
                         .join(path.strip_prefix(src_dir.join("codes/"))?.to_path_buf());
-                    log::debug!("Code \"{:?}\" has been loaded.", &key_path);
-                    codes.insert(key_path, code);
+                    targets.push((key_path, path));
                 }
             }
             Err(e) => return Err(failure::format_err!("{:?}", e)),
         }
     }
 
+    // Files in bundles embedded with relative paths are also codes keyed by the paths where they
+    // are published. Other files in bundles such as images are not highlighted.
+    for article in articles.iter() {
+        let bundle_dir = match &article.bundle_dir {
+            Some(d) => d,
+            None => continue,
+        };
+        for relative_path in article.embedded_paths() {
+            let path = bundle_dir.join(relative_path.trim_start_matches("./"));
+            if !path.is_file() {
+                continue;
+            }
+            let key_path = PathBuf::from(resolve_relative_path(
+                &article.path.to_string_lossy(),
+                relative_path,
+            ));
+            targets.push((key_path, path));
+        }
+    }
+
+    // No threads are spawned without codes, and no more than the available parallelism.
+    let num_threads = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+        .min(targets.len());
+    let mut buckets = vec![Vec::new(); num_threads];
+    for (i, target) in targets.into_iter().enumerate() {
+        buckets[i % num_threads].push(target);
+    }
+
+    let handles: Vec<_> = buckets
+        .into_iter()
+        .map(|paths| {
+            let highlighter = Arc::clone(highlighter);
+            std::thread::spawn(move || -> Result<Vec<(PathBuf, Code)>, Error> {
+                let mut codes = Vec::new();
+                for (key_path, path) in paths {
+                    let code = load_code(&path, &highlighter)?;
+                    log::debug!("Code \"{:?}\" has been loaded.", &key_path);
+                    codes.push((key_path, code));
+                }
+                Ok(codes)
+            })
+        })
+        .collect();
+
+    let mut codes = HashMap::new();
+    for handle in handles {
+        let loaded = handle
+            .join()
+            .map_err(|_| failure::format_err!("A thread highlighting codes panicked."))??;
+        codes.extend(loaded);
+    }
+
     Ok(codes)
 }
 
-fn load_code(code_path: &PathBuf) -> Result<Code, Error> {
+fn load_code(code_path: &PathBuf, highlighter: &Highlighter) -> Result<Code, Error> {
     let ext = code_path
         .extension()
         .and_then(|e| Some(e.to_string_lossy().to_string()));
 
     let mut file = File::open(code_path)?;
     let mut content = String::new();
-    file.read_to_string(&mut content)?;
+    file.read_to_string(&mut content)
+        .map_err(|e| failure::format_err!("{} in {:?}", e, code_path))?;
 
-    let highlighted_html = converter::highlight_code(&content, ext.as_ref(), code_path)?;
+    let highlighted_html =
+        converter::highlight_code(&content, ext.as_ref(), code_path, highlighter)?;
 
     Ok(Code {
         ext: ext,
@@ -1,5 +1,6 @@
 use failure::Error;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::prelude::*;
 use std::path::PathBuf;
@@ -18,12 +19,38 @@ pub struct ConfigV1 {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Blog {
     pub site_root: String,
+    #[serde(default = "utc")]
+    pub timezone: String,
+    #[serde(default)]
+    pub permalink: Option<String>,
     #[serde(default)]
     pub index_page: IndexPage,
     #[serde(default)]
     pub year_page: YearPage,
     #[serde(default)]
+    pub month_page: MonthPage,
+    #[serde(default)]
     pub tag_page: TagPage,
+    #[serde(default)]
+    pub article_page: ArticlePage,
+    #[serde(default)]
+    pub author_page: AuthorPage,
+    #[serde(default)]
+    pub excerpt: Excerpt,
+    #[serde(default)]
+    pub reading_speed: ReadingSpeed,
+    #[serde(default)]
+    pub markdown: Markdown,
+    #[serde(default)]
+    pub highlight: Highlight,
+    #[serde(default)]
+    pub authors: HashMap<String, Author>,
+    #[serde(default)]
+    pub taxonomies: HashMap<String, Taxonomy>,
+    #[serde(default)]
+    pub tags: HashMap<String, Tag>,
+    #[serde(default)]
+    pub languages: HashMap<String, Language>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -40,6 +67,13 @@ pub struct YearPage {
     pub entries_per_page: u32,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MonthPage {
+    #[serde(default = "fifteen")]
+    pub entries_per_page: u32,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(default)]
 pub struct TagPage {
@@ -47,6 +81,41 @@ pub struct TagPage {
     pub entries_per_page: u32,
 }
 
+// A tag declared in `blog.tags` config. Tags in front matter which match the key or one of
+// `aliases` are normalized to the key. `slug` overrides the one made from the key, which is
+// needed for tags like `C++` or `日記`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Tag {
+    #[serde(default)]
+    pub slug: Option<String>,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+}
+
+// A language of articles declared in `blog.languages` config. Articles in the language get their
+// own index, tag, year and feed pages under `prefix`, which is `/<key>` by default.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Language {
+    pub name: String,
+    #[serde(default)]
+    pub prefix: Option<String>,
+}
+
+impl Language {
+    // The prefix normalized to the form of `/en`, which is empty when the config is `/`.
+    pub fn prefix(&self, key: &str) -> String {
+        match self.prefix {
+            Some(ref p) if p.trim_matches('/').is_empty() => String::new(),
+            Some(ref p) => format!("/{}", p.trim_matches('/')),
+            None => format!("/{}", key),
+        }
+    }
+}
+
 impl Config {
     pub fn load(src_dir: &PathBuf) -> Result<Self, Error> {
         let config_path = if src_dir.join("salmon.yml").exists() {
@@ -88,6 +157,132 @@ impl Config {
     }
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ArticlePage {
+    #[serde(default = "five")]
+    pub related_articles: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AuthorPage {
+    #[serde(default = "fifteen")]
+    pub entries_per_page: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Author {
+    pub name: String,
+    #[serde(default)]
+    pub bio: Option<String>,
+    #[serde(default)]
+    pub avatar: Option<String>,
+    #[serde(default)]
+    pub links: Vec<AuthorLink>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthorLink {
+    pub name: String,
+    pub url: String,
+}
+
+// A taxonomy like categories which groups articles by a front matter key, in addition to the
+// built-in `tags` and `years`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Taxonomy {
+    // The front matter key which has terms of an article (default: the name of the taxonomy).
+    #[serde(default)]
+    pub front_matter: Option<String>,
+    // The name of the layout file for term pages without `.hbs`.
+    pub layout: String,
+    // The name of the layout file for the page listing all terms. The page is not built if none.
+    #[serde(default)]
+    pub list_layout: Option<String>,
+    // The URL of term pages which contains `:term` (default: `/<name>/:term`).
+    #[serde(default)]
+    pub permalink: Option<String>,
+    #[serde(default = "fifteen")]
+    pub entries_per_page: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Excerpt {
+    #[serde(default = "four")]
+    pub elements: u32,
+    #[serde(default)]
+    pub characters: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReadingSpeed {
+    #[serde(default = "two_hundred")]
+    pub words_per_minute: u32,
+    #[serde(default = "five_hundred")]
+    pub characters_per_minute: u32,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Markdown {
+    #[serde(default)]
+    pub heading_anchors: bool,
+    // Headings like `## Title {#id .class}` have the given `id` and `class` attributes.
+    #[serde(default)]
+    pub heading_attributes: bool,
+    #[serde(default)]
+    pub footnotes: bool,
+    #[serde(default)]
+    pub task_lists: bool,
+    // Quotes, dashes and ellipses are converted to typographic ones like `“`, `—` and `…`.
+    #[serde(default)]
+    pub smart_punctuation: bool,
+    // Every line break in a paragraph is written as `<br />`.
+    #[serde(default)]
+    pub hard_breaks: bool,
+}
+
+// Syntax highlighting of codes and fenced code blocks.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Highlight {
+    #[serde(default = "solarized_light")]
+    pub theme: String,
+    // Highlighted code has `class` attributes instead of `style`, and a stylesheet of `theme` and
+    // `dark_theme` is written to `stylesheet`.
+    #[serde(default)]
+    pub classes: bool,
+    // Used when a browser prefers a dark color scheme.
+    #[serde(default)]
+    pub dark_theme: Option<String>,
+    #[serde(default = "highlight_stylesheet")]
+    pub stylesheet: String,
+    // The file caching highlighted code, relative to the project directory. No cache is used if
+    // it is null.
+    #[serde(default = "highlight_cache")]
+    pub cache: Option<String>,
+}
+
+impl Blog {
+    pub fn timezone_offset(&self) -> Result<chrono::FixedOffset, Error> {
+        // chrono cannot parse an offset alone, so parse it as a part of a datetime.
+        let datetime = chrono::DateTime::parse_from_str(
+            &format!("1970-01-01T00:00:00{}", self.timezone),
+            "%Y-%m-%dT%H:%M:%S%z",
+        )
+        .map_err(|_| {
+            failure::format_err!(
+                "`{}` is not a valid timezone. Specify an offset like `+09:00`.",
+                self.timezone
+            )
+        })?;
+        Ok(*datetime.offset())
+    }
+}
+
 impl Default for IndexPage {
     fn default() -> Self {
         Self {
@@ -103,6 +298,13 @@ impl Default for YearPage {
         }
     }
 }
+impl Default for MonthPage {
+    fn default() -> Self {
+        Self {
+            entries_per_page: fifteen(),
+        }
+    }
+}
 impl Default for TagPage {
     fn default() -> Self {
         Self {
@@ -111,6 +313,64 @@ impl Default for TagPage {
     }
 }
 
+impl Default for ArticlePage {
+    fn default() -> Self {
+        Self {
+            related_articles: five(),
+        }
+    }
+}
+
+impl Default for AuthorPage {
+    fn default() -> Self {
+        Self {
+            entries_per_page: fifteen(),
+        }
+    }
+}
+
+impl Default for Excerpt {
+    fn default() -> Self {
+        Self {
+            elements: four(),
+            characters: None,
+        }
+    }
+}
+
+impl Default for ReadingSpeed {
+    fn default() -> Self {
+        Self {
+            words_per_minute: two_hundred(),
+            characters_per_minute: five_hundred(),
+        }
+    }
+}
+
+impl Default for Highlight {
+    fn default() -> Self {
+        Self {
+            theme: solarized_light(),
+            classes: false,
+            dark_theme: None,
+            stylesheet: highlight_stylesheet(),
+            cache: highlight_cache(),
+        }
+    }
+}
+
+fn utc() -> String {
+    "+00:00".to_string()
+}
+
+fn four() -> u32 {
+    4
+}
+
+fn five() -> u32 {
+    5
+}
+
 fn ten() -> u32 {
     10
 }
@@ -118,3 +378,35 @@ fn ten() -> u32 {
 fn fifteen() -> u32 {
     15
 }
+
+fn solarized_light() -> String {
+    "Solarized (light)".to_string()
+}
+
+fn highlight_stylesheet() -> String {
+    "stylesheets/highlight.css".to_string()
+}
+
+fn highlight_cache() -> Option<String> {
+    Some(".salmon-cache/highlight.json".to_string())
+}
+
+fn two_hundred() -> u32 {
+    200
+}
+
+fn five_hundred() -> u32 {
+    500
+}
+
+#[test]
+fn test_language_prefix() {
+    let language = |prefix: Option<&str>| Language {
+        name: "English".to_string(),
+        prefix: prefix.map(|p| p.to_string()),
+    };
+    assert_eq!("/en", language(None).prefix("en"));
+    assert_eq!("/english", language(Some("english/")).prefix("en"));
+    assert_eq!("/english", language(Some("/english")).prefix("en"));
+    assert_eq!("", language(Some("/")).prefix("en"));
+}
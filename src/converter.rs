@@ -1,28 +1,559 @@
+use crate::config;
 use failure::Error;
 use pulldown_cmark::html;
-use pulldown_cmark::{Options, Parser};
+use pulldown_cmark::{Event, Options, Parser, Tag};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::hash::Hasher;
 use std::path::PathBuf;
+use std::sync::Mutex;
 
-pub fn convert_to_html(body: &str) -> String {
+// Table of contents of a document. `entries` are nested by heading levels and `html` is
+// a ready-made nested list of them.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Toc {
+    entries: Vec<TocEntry>,
+    html: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TocEntry {
+    level: i32,
+    id: String,
+    title: String,
+    children: Vec<TocEntry>,
+}
+
+// Relative URLs of links and images are resolved against `link_base` if it is given. The part
+// before `<!-- more -->` is returned as an excerpt if the document has it.
+pub fn convert_to_html(
+    body: &str,
+    markdown_config: &config::Markdown,
+    highlighter: &Highlighter,
+    link_base: Option<&String>,
+) -> (String, Toc, Option<String>) {
     let mut options = Options::empty();
     options.insert(Options::ENABLE_STRIKETHROUGH);
     options.insert(Options::ENABLE_TABLES);
-    let parser = Parser::new_ext(body, options);
+    if markdown_config.footnotes {
+        options.insert(Options::ENABLE_FOOTNOTES);
+    }
+    if markdown_config.task_lists {
+        options.insert(Options::ENABLE_TASKLISTS);
+    }
+    let mut events: Vec<_> = Parser::new_ext(body, options).collect();
+    if markdown_config.smart_punctuation {
+        smarten_punctuation(&mut events);
+    }
+    if markdown_config.hard_breaks {
+        for event in events.iter_mut() {
+            if let Event::SoftBreak = event {
+                *event = Event::HardBreak;
+            }
+        }
+    }
+    let headings = add_heading_ids(
+        &mut events,
+        markdown_config.heading_anchors,
+        markdown_config.heading_attributes,
+    );
+    if let Some(base) = link_base {
+        resolve_relative_links(&mut events, base);
+    }
+    if markdown_config.footnotes {
+        events = move_footnotes_to_end(events);
+    }
+    events = highlight_code_blocks(events, highlighter);
 
+    let more = find_more_marker(&events);
     let mut built_html = String::with_capacity(body.len() * 3 / 2);
-    html::push_html(&mut built_html, parser);
+    let mut excerpt = None;
+    let mut events = events.into_iter();
+    if let Some(i) = more {
+        html::push_html(&mut built_html, events.by_ref().take(i));
+        excerpt = Some(built_html.clone());
+    }
+    html::push_html(&mut built_html, events);
+
+    (built_html, Toc::new(headings), excerpt)
+}
+
+// Find `<!-- more -->` which is a block by itself at the top level, so that an excerpt never
+// ends in the middle of an element. Markers in paragraphs, lists or code are ignored.
+fn find_more_marker(events: &[Event]) -> Option<usize> {
+    let mut depth = 0;
+    for (i, event) in events.iter().enumerate() {
+        match event {
+            Event::Start(Tag::HtmlBlock) => {
+                if depth == 0 {
+                    let mut html = String::new();
+                    for event in events[i + 1..].iter() {
+                        match event {
+                            Event::Html(h) => html.push_str(h),
+                            _ => break,
+                        }
+                    }
+                    // Comments like `<!-->` are too short to be stripped as both ends.
+                    let comment = html
+                        .trim()
+                        .strip_prefix("<!--")
+                        .and_then(|h| h.strip_suffix("-->"));
+                    if comment.map(str::trim) == Some("more") {
+                        return Some(i);
+                    }
+                }
+                depth += 1;
+            }
+            Event::Start(_) => depth += 1,
+            Event::End(_) => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+// Replace heading tags with raw HTML which has a unique `id` attribute and returns
+// flat TOC entries of the headings. With `with_attributes`, trailing `{#id .class}` of a heading
+// gives its `id` and `class` attributes.
+fn add_heading_ids(
+    events: &mut [Event],
+    with_anchor: bool,
+    with_attributes: bool,
+) -> Vec<TocEntry> {
+    let mut headings = Vec::new();
+    let mut ids = HashSet::new();
+    let mut start = None;
+    let mut last_text = None;
+    let mut title = String::new();
+
+    for i in 0..events.len() {
+        let level = match events[i] {
+            Event::Start(Tag::Header(_)) => {
+                start = Some(i);
+                last_text = None;
+                title.clear();
+                continue;
+            }
+            Event::Text(ref t) | Event::Code(ref t) => {
+                if start.is_some() {
+                    title.push_str(t);
+                    last_text = Some(i);
+                }
+                continue;
+            }
+            Event::End(Tag::Header(level)) => level,
+            _ => continue,
+        };
+
+        let mut attributes = HeadingAttributes::default();
+        if let (true, Some(j)) = (with_attributes, last_text) {
+            let split = match events[j] {
+                Event::Text(ref t) => split_heading_attributes(t).map(|s| (t.len(), s)),
+                _ => None,
+            };
+            if let Some((len, (text, a))) = split {
+                title.truncate(title.len() - len);
+                title.push_str(&text);
+                events[j] = Event::Text(text.into());
+                attributes = a;
+            }
+        }
+        let id = unique_id(attributes.id.unwrap_or_else(|| slugify(&title)), &mut ids);
+        let class = if attributes.classes.is_empty() {
+            String::new()
+        } else {
+            format!(" class=\"{}\"", attributes.classes.join(" "))
+        };
+        let closing_tag = if with_anchor {
+            format!("<a class=\"anchor\" href=\"#{}\">#</a></h{}>\n", id, level)
+        } else {
+            format!("</h{}>\n", level)
+        };
+        events[start.take().unwrap()] =
+            Event::Html(format!("<h{} id=\"{}\"{}>", level, id, class).into());
+        events[i] = Event::Html(closing_tag.into());
+        headings.push(TocEntry {
+            level,
+            id,
+            title: title.clone(),
+            children: Vec::new(),
+        });
+    }
+
+    headings
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct HeadingAttributes {
+    id: Option<String>,
+    classes: Vec<String>,
+}
+
+// Split text at the end of a heading like `Title {#id .class}` into `Title` and its attributes.
+// Values of attributes consist of ASCII alphanumerics, `-` and `_`, otherwise the text is kept
+// as it is.
+fn split_heading_attributes(text: &str) -> Option<(String, HeadingAttributes)> {
+    let text = text.trim_end();
+    if !text.ends_with('}') {
+        return None;
+    }
+    let open = text.rfind('{')?;
+    let is_valid = |value: &str| {
+        !value.is_empty()
+            && value
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    };
+    let mut attributes = HeadingAttributes::default();
+    for attribute in text[open + 1..text.len() - 1].split_whitespace() {
+        if attribute.starts_with('#') && is_valid(&attribute[1..]) {
+            attributes.id = Some(attribute[1..].to_string());
+        } else if attribute.starts_with('.') && is_valid(&attribute[1..]) {
+            attributes.classes.push(attribute[1..].to_string());
+        } else {
+            return None;
+        }
+    }
+    if attributes == HeadingAttributes::default() {
+        return None;
+    }
+    Some((text[..open].trim_end().to_string(), attributes))
+}
+
+// Replace straight quotes, `--`, `---` and `...` in text with typographic ones. Text in code is
+// kept as it is.
+fn smarten_punctuation(events: &mut [Event]) {
+    let mut in_code_block = false;
+    // The character before the current text, which decides whether a quote opens or closes.
+    let mut prev = None;
+    for event in events.iter_mut() {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => in_code_block = true,
+            Event::End(Tag::CodeBlock(_)) => in_code_block = false,
+            Event::Text(ref mut text) if !in_code_block => {
+                let (smartened, last) = smarten(text, prev);
+                *text = smartened.into();
+                prev = last;
+            }
+            Event::Code(_) | Event::InlineHtml(_) | Event::FootnoteReference(_) => prev = Some('x'),
+            // Quotes next to inline markups such as `*"emphasis"*` are decided by the text
+            // around them. Others are at the beginning of a block or a line.
+            Event::Start(Tag::Emphasis)
+            | Event::End(Tag::Emphasis)
+            | Event::Start(Tag::Strong)
+            | Event::End(Tag::Strong)
+            | Event::Start(Tag::Strikethrough)
+            | Event::End(Tag::Strikethrough)
+            | Event::Start(Tag::Link(..))
+            | Event::End(Tag::Link(..)) => {}
+            _ => prev = None,
+        }
+    }
+}
+
+fn smarten(text: &str, mut prev: Option<char>) -> (String, Option<char>) {
+    let chars: Vec<char> = text.chars().collect();
+    let mut smartened = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let (c, len) = match chars[i] {
+            '-' if chars[i..].starts_with(&['-', '-', '-']) => ('\u{2014}', 3),
+            '-' if chars[i..].starts_with(&['-', '-']) => ('\u{2013}', 2),
+            '.' if chars[i..].starts_with(&['.', '.', '.']) => ('\u{2026}', 3),
+            '\'' if opens_quote(prev) => ('\u{2018}', 1),
+            '\'' => ('\u{2019}', 1),
+            '"' if opens_quote(prev) => ('\u{201c}', 1),
+            '"' => ('\u{201d}', 1),
+            c => (c, 1),
+        };
+        smartened.push(c);
+        prev = Some(c);
+        i += len;
+    }
+    (smartened, prev)
+}
 
-    built_html
+fn opens_quote(prev: Option<char>) -> bool {
+    match prev {
+        Some(c) => c.is_whitespace() || "([{\u{2014}\u{2013}\u{2018}\u{201c}".contains(c),
+        None => true,
+    }
+}
+
+// Move footnote definitions to a list at the end of the document. Footnotes are numbered in the
+// order of their first references, and each of them has a link back to the reference.
+fn move_footnotes_to_end(events: Vec<Event>) -> Vec<Event> {
+    let mut body = Vec::with_capacity(events.len());
+    let mut definitions = HashMap::new();
+    let mut definition: Option<(String, Vec<Event>)> = None;
+    for event in events {
+        match event {
+            Event::Start(Tag::FootnoteDefinition(label)) => {
+                definition = Some((label.to_string(), Vec::new()));
+            }
+            Event::End(Tag::FootnoteDefinition(_)) => {
+                if let Some((label, events)) = definition.take() {
+                    definitions.insert(label, events);
+                }
+            }
+            event => match definition {
+                Some((_, ref mut events)) => events.push(event),
+                None => body.push(event),
+            },
+        }
+    }
+
+    let mut labels = Vec::new();
+    let mut events = number_footnote_references(body, &definitions, &mut labels);
+    let mut footnotes = Vec::new();
+    // Footnotes can refer to other footnotes, which are appended to `labels` while looping.
+    let mut i = 0;
+    while i < labels.len() {
+        let (label, id) = labels[i].clone();
+        let mut definition =
+            number_footnote_references(definitions[&label].clone(), &definitions, &mut labels);
+        let backref = Event::Html(
+            format!(
+                " <a class=\"footnote-backref\" href=\"#fnref-{}\">\u{21a9}</a>",
+                id
+            )
+            .into(),
+        );
+        // Put the link at the end of the last paragraph if possible.
+        match definition.pop() {
+            Some(Event::End(Tag::Paragraph)) => {
+                definition.push(backref);
+                definition.push(Event::End(Tag::Paragraph));
+            }
+            Some(event) => {
+                definition.push(event);
+                definition.push(backref);
+            }
+            None => definition.push(backref),
+        }
+        footnotes.push(Event::Html(format!("<li id=\"fn-{}\">\n", id).into()));
+        footnotes.extend(definition);
+        footnotes.push(Event::Html("</li>\n".into()));
+        i += 1;
+    }
+    if !footnotes.is_empty() {
+        events.push(Event::Html("<section class=\"footnotes\">\n<ol>\n".into()));
+        events.extend(footnotes);
+        events.push(Event::Html("</ol>\n</section>\n".into()));
+    }
+    events
+}
+
+// Replace references to footnotes with their numbers linked to the footnotes. `labels` has
+// labels of footnotes and their IDs in order of their numbers. IDs are made from labels, and
+// numbered like headings when labels such as `a b` and `a-b` make the same one. References to
+// undefined footnotes are left as text.
+fn number_footnote_references<'a>(
+    events: Vec<Event<'a>>,
+    definitions: &HashMap<String, Vec<Event<'a>>>,
+    labels: &mut Vec<(String, String)>,
+) -> Vec<Event<'a>> {
+    events
+        .into_iter()
+        .map(|event| match event {
+            Event::FootnoteReference(label) => {
+                if !definitions.contains_key(&*label) {
+                    return Event::Text(format!("[^{}]", label).into());
+                }
+                // Only the first reference has `id` to be linked back from the footnote.
+                let (number, id, id_attribute) =
+                    match labels.iter().position(|(l, _)| **l == *label) {
+                        Some(i) => (i + 1, labels[i].1.clone(), String::new()),
+                        None => {
+                            let mut ids = labels.iter().map(|(_, id)| id.clone()).collect();
+                            let id = unique_id(slugify(&label), &mut ids);
+                            labels.push((label.to_string(), id.clone()));
+                            (labels.len(), id.clone(), format!(" id=\"fnref-{}\"", id))
+                        }
+                    };
+                Event::Html(
+                    format!(
+                        "<sup class=\"footnote-reference\"{}><a href=\"#fn-{}\">{}</a></sup>",
+                        id_attribute, id, number
+                    )
+                    .into(),
+                )
+            }
+            event => event,
+        })
+        .collect()
+}
+
+// Replace fenced code blocks which have a language in their info strings like ```` ```rust ````
+// with highlighted HTML. Other code blocks are kept as they are.
+fn highlight_code_blocks<'a>(events: Vec<Event<'a>>, highlighter: &Highlighter) -> Vec<Event<'a>> {
+    let mut highlighted = Vec::with_capacity(events.len());
+    let mut events = events.into_iter();
+    while let Some(event) = events.next() {
+        let info = match event {
+            Event::Start(Tag::CodeBlock(ref info)) => info.to_string(),
+            _ => {
+                highlighted.push(event);
+                continue;
+            }
+        };
+        let mut block = vec![event];
+        let mut content = String::new();
+        for event in &mut events {
+            match event {
+                Event::End(Tag::CodeBlock(_)) => {
+                    block.push(event);
+                    break;
+                }
+                Event::Text(ref text) => content.push_str(text),
+                _ => {}
+            }
+            block.push(event);
+        }
+
+        // Info strings can have attributes after the language like `rust,ignore`.
+        let lang = info
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .next()
+            .unwrap_or("");
+        if lang.is_empty() {
+            highlighted.extend(block);
+            continue;
+        }
+        match highlighter.syntax_set.find_syntax_by_token(lang) {
+            Some(sr) => highlighted.push(Event::Html(highlighter.highlight(&content, sr).into())),
+            None => {
+                log::warn!(
+                    "Cannot determine syntax `{}` of a code block. It is not highlighted.",
+                    lang
+                );
+                highlighted.extend(block);
+            }
+        }
+    }
+    highlighted
+}
+
+// Make an ID like GitHub does. Alphanumeric characters including non-ASCII ones are kept,
+// spaces are replaced with `-` and other characters are dropped.
+pub fn slugify(title: &str) -> String {
+    let mut slug = String::with_capacity(title.len());
+    for c in title.trim().chars() {
+        if c.is_alphanumeric() {
+            slug.extend(c.to_lowercase());
+        } else if c == ' ' || c == '-' || c == '_' {
+            slug.push('-');
+        }
+    }
+    if slug.is_empty() {
+        "section".to_string()
+    } else {
+        slug
+    }
+}
+
+fn resolve_relative_links(events: &mut [Event], base: &str) {
+    for event in events.iter_mut() {
+        match event {
+            Event::Start(Tag::Link(_, ref mut url, _))
+            | Event::End(Tag::Link(_, ref mut url, _))
+            | Event::Start(Tag::Image(_, ref mut url, _))
+            | Event::End(Tag::Image(_, ref mut url, _))
+                if is_relative_url(url) =>
+            {
+                *url = format!("{}{}", base, url.trim_start_matches("./")).into();
+            }
+            _ => {}
+        }
+    }
+}
+
+// URLs like `photo.jpg` and `./photo.jpg` are relative, but ones like `/photo.jpg`, `#section`
+// and `https://example.com/` are not.
+fn is_relative_url(url: &str) -> bool {
+    if url.is_empty() || url.starts_with('/') || url.starts_with('#') {
+        return false;
+    }
+    match url.find(':') {
+        Some(i) => url[..i].contains(&['/', '?', '#'][..]),
+        None => true,
+    }
+}
+
+fn unique_id(slug: String, ids: &mut HashSet<String>) -> String {
+    let mut id = slug.clone();
+    let mut i = 1;
+    while ids.contains(&id) {
+        id = format!("{}-{}", slug, i);
+        i += 1;
+    }
+    ids.insert(id.clone());
+    id
+}
+
+impl Toc {
+    fn new(headings: Vec<TocEntry>) -> Self {
+        let mut entries: Vec<TocEntry> = Vec::new();
+        for heading in headings {
+            let mut siblings = &mut entries;
+            // Dig into the last entry while it is a parent of the heading.
+            while siblings
+                .last()
+                .map(|e| e.level < heading.level)
+                .unwrap_or(false)
+            {
+                siblings = &mut siblings.last_mut().unwrap().children;
+            }
+            siblings.push(heading);
+        }
+
+        let mut html = String::new();
+        write_toc_html(&mut html, &entries);
+
+        Toc { entries, html }
+    }
+}
+
+fn write_toc_html(html: &mut String, entries: &[TocEntry]) {
+    if entries.is_empty() {
+        return;
+    }
+    html.push_str("<ul>\n");
+    for entry in entries {
+        html.push_str(&format!(
+            "<li><a href=\"#{}\">{}</a>",
+            entry.id,
+            handlebars::html_escape(&entry.title)
+        ));
+        if !entry.children.is_empty() {
+            html.push('\n');
+            write_toc_html(html, &entry.children);
+        }
+        html.push_str("</li>\n");
+    }
+    html.push_str("</ul>\n");
+}
+
+// Collect text in a markdown document, dropping its markup.
+pub fn extract_text(body: &str) -> String {
+    let mut text = String::with_capacity(body.len());
+    for event in Parser::new(body) {
+        match event {
+            Event::Text(t) | Event::Code(t) => text.push_str(&t),
+            Event::SoftBreak | Event::HardBreak | Event::End(_) => text.push('\n'),
+            _ => { /* do nothing */ }
+        }
+    }
+    text
 }
 
 pub fn highlight_code(
     content: &str,
     ext: Option<&String>,
     code_path: &PathBuf,
+    highlighter: &Highlighter,
 ) -> Result<String, Error> {
-    let ss = syntect::parsing::SyntaxSet::load_defaults_newlines();
-    let ts = syntect::highlighting::ThemeSet::load_defaults();
-    let theme = &ts.themes["Solarized (light)"];
+    let ss = &highlighter.syntax_set;
     let sr = match ss.find_syntax_by_first_line(content) {
         Some(s) => s,
         None => {
@@ -47,7 +578,620 @@ pub fn highlight_code(
         }
     };
 
-    Ok(syntect::html::highlighted_html_for_string(
-        content, &ss, &sr, &theme,
-    ))
+    Ok(highlighter.highlight(content, sr))
+}
+
+// Syntaxes and themes to highlight code. `syntaxes/*.sublime-syntax` and `themes/*.tmTheme` in
+// the project are added to the defaults of syntect. They are loaded once per build and shared by
+// threads highlighting codes.
+pub struct Highlighter {
+    syntax_set: syntect::parsing::SyntaxSet,
+    // Themes chosen by `theme` and `dark_theme` config.
+    theme: syntect::highlighting::Theme,
+    dark_theme: Option<syntect::highlighting::Theme>,
+    config: config::Highlight,
+    // A digest of the version, the config, syntaxes and themes, which invalidates the cache.
+    fingerprint: u64,
+    cache_path: Option<PathBuf>,
+    cache: HighlightCache,
+    // Highlighted HTML in this build, which is saved as the next cache.
+    highlighted: Mutex<HashMap<String, CacheEntry>>,
+}
+
+// Highlighted HTML keyed by the hash of syntax names and code.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct HighlightCache {
+    fingerprint: u64,
+    entries: HashMap<String, CacheEntry>,
+}
+
+// The scope and the code are kept to check them, because different code can have the same hash.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    scope: String,
+    content: String,
+    html: String,
+}
+
+// 64-bit FNV-1a, whose hashes do not change across Rust releases unlike `DefaultHasher`, so
+// they can be saved to the cache.
+struct StableHasher(u64);
+
+impl StableHasher {
+    fn new() -> Self {
+        StableHasher(0xcbf2_9ce4_8422_2325)
+    }
+
+    // Write a string terminated by a null character, so that `ab` `c` differs from `a` `bc`.
+    fn write_str(&mut self, s: &str) {
+        self.write(s.as_bytes());
+        self.write_u8(0);
+    }
+}
+
+impl Hasher for StableHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 ^= u64::from(*b);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+impl Highlighter {
+    pub fn load(src_dir: &PathBuf, highlight_config: &config::Highlight) -> Result<Self, Error> {
+        let mut syntax_set = syntect::parsing::SyntaxSet::load_defaults_newlines();
+        let syntax_dir = src_dir.join("syntaxes");
+        if syntax_dir.is_dir() {
+            log::info!("Loading syntaxes: {:?}", syntax_dir);
+            let mut builder = syntax_set.into_builder();
+            builder
+                .add_from_folder(&syntax_dir, true)
+                .map_err(|e| failure::format_err!("{:?} in {:?}", e, syntax_dir))?;
+            syntax_set = builder.build();
+        }
+        let mut theme_set = syntect::highlighting::ThemeSet::load_defaults();
+        let theme_dir = src_dir.join("themes");
+        if theme_dir.is_dir() {
+            log::info!("Loading themes: {:?}", theme_dir);
+            theme_set
+                .add_from_folder(&theme_dir)
+                .map_err(|e| failure::format_err!("{:?} in {:?}", e, theme_dir))?;
+        }
+
+        // Find themes before highlighting anything.
+        let find_theme = |name: &String| {
+            theme_set.themes.get(name).cloned().ok_or_else(|| {
+                let mut names: Vec<_> = theme_set.themes.keys().collect();
+                names.sort();
+                failure::format_err!(
+                    "Theme `{}` in `blog.highlight` config is not found. Available themes are {:?}.",
+                    name,
+                    names
+                )
+            })
+        };
+        let theme = find_theme(&highlight_config.theme)?;
+        let dark_theme = match highlight_config.dark_theme {
+            Some(ref name) => Some(find_theme(name)?),
+            None => None,
+        };
+
+        let mut hasher = StableHasher::new();
+        hasher.write_str(env!("CARGO_PKG_VERSION"));
+        hasher.write_str(&highlight_config.theme);
+        hasher.write_u8(highlight_config.classes as u8);
+        for dir in [syntax_dir, theme_dir].iter() {
+            for path in glob::glob(&dir.join("**/*").to_string_lossy())? {
+                let path = path?;
+                if path.is_file() {
+                    hasher.write_str(&path.to_string_lossy());
+                    hasher.write(&std::fs::read(&path)?);
+                    hasher.write_u8(0);
+                }
+            }
+        }
+
+        Ok(Highlighter {
+            syntax_set,
+            theme,
+            dark_theme,
+            config: highlight_config.clone(),
+            fingerprint: hasher.finish(),
+            cache_path: None,
+            cache: HighlightCache::default(),
+            highlighted: Mutex::new(HashMap::new()),
+        })
+    }
+
+    // Use highlighted HTML saved in `cache_path` by the last build. The cache is ignored if it is
+    // broken or made with other syntaxes, themes or config.
+    pub fn load_cache(&mut self, cache_path: PathBuf) {
+        if let Ok(json) = std::fs::read_to_string(&cache_path) {
+            match serde_json::from_str::<HighlightCache>(&json) {
+                Ok(ref cache) if cache.fingerprint != self.fingerprint => {
+                    log::info!("Highlight cache {:?} is outdated.", cache_path);
+                }
+                Ok(cache) => {
+                    log::debug!("Highlight cache {:?} has been loaded.", cache_path);
+                    self.cache = cache;
+                }
+                Err(e) => log::warn!("Ignoring broken highlight cache {:?}: {}", cache_path, e),
+            }
+        }
+        self.cache_path = Some(cache_path);
+    }
+
+    // Write code highlighted in this build to the cache. Code which is no longer used is dropped.
+    pub fn save_cache(&self) -> Result<(), Error> {
+        let cache_path = match self.cache_path {
+            Some(ref p) => p,
+            None => return Ok(()),
+        };
+        let cache = HighlightCache {
+            fingerprint: self.fingerprint,
+            entries: self.highlighted.lock().unwrap().clone(),
+        };
+        if let Some(dir) = cache_path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(cache_path, serde_json::to_string(&cache)?)?;
+        log::debug!("Highlight cache {:?} has been saved.", cache_path);
+        Ok(())
+    }
+
+    // Highlighted HTML is reused if the same code was highlighted by the last build.
+    fn highlight(&self, content: &str, sr: &syntect::parsing::SyntaxReference) -> String {
+        let scope = sr.scope.build_string();
+        let mut hasher = StableHasher::new();
+        hasher.write_str(&scope);
+        hasher.write_str(content);
+        let key = format!("{:016x}", hasher.finish());
+
+        let html = match self.cache.entries.get(&key) {
+            Some(entry) if entry.scope == scope && entry.content == content => entry.html.clone(),
+            _ => self.highlight_uncached(content, sr),
+        };
+        let entry = CacheEntry {
+            scope,
+            content: content.to_string(),
+            html: html.clone(),
+        };
+        self.highlighted.lock().unwrap().insert(key, entry);
+        html
+    }
+
+    // Highlight code with `style` attributes of the theme, or `class` attributes of scopes which
+    // are styled by `stylesheet`.
+    fn highlight_uncached(&self, content: &str, sr: &syntect::parsing::SyntaxReference) -> String {
+        if !self.config.classes {
+            return syntect::html::highlighted_html_for_string(
+                content,
+                &self.syntax_set,
+                sr,
+                &self.theme,
+            );
+        }
+        let mut generator = syntect::html::ClassedHTMLGenerator::new(sr, &self.syntax_set);
+        for line in syntect::util::LinesWithEndings::from(content) {
+            generator.parse_html_for_line(line);
+        }
+        format!(
+            "<pre class=\"highlight\">\n{}</pre>\n",
+            generator.finalize()
+        )
+    }
+
+    // The stylesheet for code highlighted with `class` attributes. The dark theme is applied when
+    // a browser prefers a dark color scheme.
+    pub fn stylesheet(&self) -> Option<String> {
+        if !self.config.classes {
+            return None;
+        }
+        let mut css = theme_css(&self.theme);
+        if let Some(ref dark_theme) = self.dark_theme {
+            css.push_str("@media (prefers-color-scheme: dark) {\n");
+            css.push_str(&theme_css(dark_theme));
+            css.push_str("}\n");
+        }
+        Some(css)
+    }
+}
+
+// Rules for `.highlight` and spans in it. Only theme items of single scopes like
+// `entity.name.function` are supported, which match spans with `class="entity name function"`.
+fn theme_css(theme: &syntect::highlighting::Theme) -> String {
+    let mut css = format!(
+        ".highlight {{\n{}}}\n",
+        css_declarations(&syntect::highlighting::StyleModifier {
+            foreground: theme.settings.foreground,
+            background: theme.settings.background,
+            font_style: None,
+        })
+    );
+    for item in theme.scopes.iter() {
+        let selectors: Vec<_> = item
+            .scope
+            .selectors
+            .iter()
+            .filter_map(|s| s.extract_single_scope())
+            .map(|s| format!(".highlight .{}", s.build_string()))
+            .collect();
+        if selectors.is_empty() {
+            continue;
+        }
+        css.push_str(&format!(
+            "{} {{\n{}}}\n",
+            selectors.join(", "),
+            css_declarations(&item.style)
+        ));
+    }
+    css
+}
+
+fn css_declarations(style: &syntect::highlighting::StyleModifier) -> String {
+    use syntect::highlighting::FontStyle;
+
+    let mut declarations = String::new();
+    if let Some(color) = style.foreground {
+        declarations.push_str(&format!("  color: {};\n", css_color(color)));
+    }
+    if let Some(color) = style.background {
+        declarations.push_str(&format!("  background-color: {};\n", css_color(color)));
+    }
+    if let Some(font_style) = style.font_style {
+        if font_style.contains(FontStyle::BOLD) {
+            declarations.push_str("  font-weight: bold;\n");
+        }
+        if font_style.contains(FontStyle::ITALIC) {
+            declarations.push_str("  font-style: italic;\n");
+        }
+        if font_style.contains(FontStyle::UNDERLINE) {
+            declarations.push_str("  text-decoration: underline;\n");
+        }
+    }
+    declarations
+}
+
+fn css_color(color: syntect::highlighting::Color) -> String {
+    if color.a == 0xff {
+        format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b)
+    } else {
+        format!(
+            "rgba({}, {}, {}, {:.3})",
+            color.r,
+            color.g,
+            color.b,
+            f64::from(color.a) / 255.0
+        )
+    }
+}
+
+#[test]
+fn test_convert_to_html_with_toc() {
+    let markdown_config = config::Markdown::default();
+    let (html, toc, _) = convert_to_html(
+        "# Intro\n\n## Hello, `world`!\n\n## Hello world\n\n### うさぎ\n\n# Intro\n",
+        &markdown_config,
+        &Highlighter::load(&PathBuf::from("example"), &config::Highlight::default()).unwrap(),
+        None,
+    );
+    assert_eq!(
+        "<h1 id=\"intro\">Intro</h1>\n<h2 id=\"hello-world\">Hello, <code>world</code>!</h2>\n<h2 id=\"hello-world-1\">Hello world</h2>\n<h3 id=\"うさぎ\">うさぎ</h3>\n<h1 id=\"intro-1\">Intro</h1>\n",
+        html
+    );
+    assert_eq!(2, toc.entries.len());
+    assert_eq!(2, toc.entries[0].children.len());
+    assert_eq!("うさぎ", toc.entries[0].children[1].children[0].title);
+    assert_eq!(
+        "<ul>\n<li><a href=\"#intro\">Intro</a>\n<ul>\n<li><a href=\"#hello-world\">Hello, world!</a></li>\n<li><a href=\"#hello-world-1\">Hello world</a>\n<ul>\n<li><a href=\"#うさぎ\">うさぎ</a></li>\n</ul>\n</li>\n</ul>\n</li>\n<li><a href=\"#intro-1\">Intro</a></li>\n</ul>\n",
+        toc.html
+    );
+
+    let markdown_config = config::Markdown {
+        heading_anchors: true,
+        ..Default::default()
+    };
+    let (html, _, _) = convert_to_html(
+        "## Hello\n",
+        &markdown_config,
+        &Highlighter::load(&PathBuf::from("example"), &config::Highlight::default()).unwrap(),
+        None,
+    );
+    assert_eq!(
+        "<h2 id=\"hello\">Hello<a class=\"anchor\" href=\"#hello\">#</a></h2>\n",
+        html
+    );
+}
+
+#[test]
+fn test_convert_to_html_with_link_base() {
+    let (html, _, _) = convert_to_html(
+        "[a](photo.jpg) [b](./files/c.txt) [c](/about.html) [d](#top) [e](https://example.com/)\n\n![f](photo.jpg)\n",
+        &config::Markdown::default(),
+        &Highlighter::load(&PathBuf::from("example"), &config::Highlight::default()).unwrap(),
+        Some(&"/2019/06/23/post/".to_string()),
+    );
+    assert_eq!(
+        "<p><a href=\"/2019/06/23/post/photo.jpg\">a</a> <a href=\"/2019/06/23/post/files/c.txt\">b</a> <a href=\"/about.html\">c</a> <a href=\"#top\">d</a> <a href=\"https://example.com/\">e</a></p>\n<p><img src=\"/2019/06/23/post/photo.jpg\" alt=\"f\" /></p>\n",
+        html
+    );
+}
+
+#[test]
+fn test_convert_to_html_with_excerpt() {
+    let highlighter =
+        Highlighter::load(&PathBuf::from("example"), &config::Highlight::default()).unwrap();
+    let convert = |body| convert_to_html(body, &config::Markdown::default(), &highlighter, None);
+
+    let (html, _, excerpt) = convert("foo\n\n<!-- more -->\n\nbar\n");
+    assert_eq!("<p>foo</p>\n<!-- more -->\n<p>bar</p>\n", html);
+    assert_eq!(Some("<p>foo</p>\n".to_string()), excerpt);
+    let (_, _, excerpt) = convert("<!-- foo -->\n\nfoo\n\n<!--more-->\n\nbar\n");
+    assert_eq!(Some("<!-- foo -->\n<p>foo</p>\n".to_string()), excerpt);
+
+    // Markers which are not blocks at the top level are ignored.
+    assert_eq!(None, convert("foo <!-- more --> bar\n").2);
+    assert_eq!(None, convert("- foo\n\n  <!-- more -->\n- bar\n").2);
+    assert_eq!(None, convert("```\n<!-- more -->\n```\n").2);
+
+    // Short comments are not markers.
+    assert_eq!(None, convert("foo\n\n<!-->\n\nbar\n").2);
+    assert_eq!(None, convert("foo\n\n<!--->\n\nbar\n").2);
+}
+
+#[test]
+fn test_convert_to_html_with_footnotes() {
+    let markdown_config = config::Markdown {
+        footnotes: true,
+        ..Default::default()
+    };
+    let (html, _, _) = convert_to_html(
+        "Salmon[^salmon] and trout[^1][^none].\n\n[^1]: A fish.\n\n[^salmon]: Another fish[^1].\n",
+        &markdown_config,
+        &Highlighter::load(&PathBuf::from("example"), &config::Highlight::default()).unwrap(),
+        None,
+    );
+    assert_eq!(
+        "<p>Salmon<sup class=\"footnote-reference\" id=\"fnref-salmon\"><a href=\"#fn-salmon\">1</a></sup> and trout<sup class=\"footnote-reference\" id=\"fnref-1\"><a href=\"#fn-1\">2</a></sup>[^none].</p>\n<section class=\"footnotes\">\n<ol>\n<li id=\"fn-salmon\">\n<p>Another fish<sup class=\"footnote-reference\"><a href=\"#fn-1\">2</a></sup>. <a class=\"footnote-backref\" href=\"#fnref-salmon\">\u{21a9}</a></p>\n</li>\n<li id=\"fn-1\">\n<p>A fish. <a class=\"footnote-backref\" href=\"#fnref-1\">\u{21a9}</a></p>\n</li>\n</ol>\n</section>\n",
+        html
+    );
+
+    // Labels which make the same ID are numbered.
+    let (html, _, _) = convert_to_html(
+        "A[^a b] B[^a-b].\n\n[^a b]: A.\n\n[^a-b]: B.\n",
+        &markdown_config,
+        &Highlighter::load(&PathBuf::from("example"), &config::Highlight::default()).unwrap(),
+        None,
+    );
+    assert!(html.contains(
+        "<sup class=\"footnote-reference\" id=\"fnref-a-b\"><a href=\"#fn-a-b\">1</a></sup>"
+    ));
+    assert!(html.contains(
+        "<sup class=\"footnote-reference\" id=\"fnref-a-b-1\"><a href=\"#fn-a-b-1\">2</a></sup>"
+    ));
+    assert!(html
+        .contains("<li id=\"fn-a-b\">\n<p>A. <a class=\"footnote-backref\" href=\"#fnref-a-b\">"));
+    assert!(html.contains(
+        "<li id=\"fn-a-b-1\">\n<p>B. <a class=\"footnote-backref\" href=\"#fnref-a-b-1\">"
+    ));
+}
+
+#[test]
+fn test_convert_to_html_with_extensions() {
+    let markdown_config = config::Markdown {
+        heading_attributes: true,
+        task_lists: true,
+        smart_punctuation: true,
+        hard_breaks: true,
+        ..Default::default()
+    };
+    let (html, toc, _) = convert_to_html(
+        "## It's \"salmon\" {#salmon .fish}\n\n- [x] Done -- or not...\n- [ ] *\"Todo\"*\nnext---line\n\n`\"code\"`\n",
+        &markdown_config,
+        &Highlighter::load(&PathBuf::from("example"), &config::Highlight::default()).unwrap(),
+        None,
+    );
+    assert_eq!(
+        "<h2 id=\"salmon\" class=\"fish\">It\u{2019}s \u{201c}salmon\u{201d}</h2>\n<ul>\n<li><input disabled=\"\" type=\"checkbox\" checked=\"\"/>\nDone \u{2013} or not\u{2026}</li>\n<li><input disabled=\"\" type=\"checkbox\"/>\n<em>\u{201c}Todo\u{201d}</em><br />\nnext\u{2014}line</li>\n</ul>\n<p><code>&quot;code&quot;</code></p>\n",
+        html
+    );
+    assert_eq!("It\u{2019}s \u{201c}salmon\u{201d}", toc.entries[0].title);
+
+    // Attributes with other characters are not attributes but text.
+    let (html, _, _) = convert_to_html(
+        "## A {#a\"b}\n\n## B {.c<d}\n",
+        &config::Markdown {
+            heading_attributes: true,
+            ..Default::default()
+        },
+        &Highlighter::load(&PathBuf::from("example"), &config::Highlight::default()).unwrap(),
+        None,
+    );
+    assert_eq!(
+        "<h2 id=\"a-ab\">A {#a&quot;b}</h2>\n<h2 id=\"b-cd\">B {.c&lt;d}</h2>\n",
+        html
+    );
+}
+
+#[test]
+fn test_convert_to_html_with_code_blocks() {
+    let (html, _, _) = convert_to_html(
+        "```rust,ignore\nfn main() {}\n```\n\n```unknown\nfoo\n```\n\n    plain\n\n`inline`\n",
+        &config::Markdown::default(),
+        &Highlighter::load(&PathBuf::from("example"), &config::Highlight::default()).unwrap(),
+        None,
+    );
+    assert_eq!(
+        "<pre style=\"background-color:#fdf6e3;\">\n<span style=\"color:#268bd2;\">fn </span><span style=\"color:#b58900;\">main</span><span style=\"color:#657b83;\">() {}\n</span></pre>\n<pre><code class=\"language-unknown\">foo\n</code></pre>\n<pre><code>plain\n</code></pre>\n<p><code>inline</code></p>\n",
+        html
+    );
+}
+
+#[test]
+fn test_highlighter_with_custom_syntaxes_and_themes() {
+    let fixture_dir = PathBuf::from("tests/fixtures/highlight");
+    let highlighter = Highlighter::load(
+        &fixture_dir,
+        &config::Highlight {
+            classes: true,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    let keyword = "<span class=\"keyword other toy\">let</span>";
+
+    // Fenced blocks find the syntax by its extension and its name.
+    for lang in &["toy", "Toy"] {
+        let (html, _, _) = convert_to_html(
+            &format!("```{}\nlet x = 1 # one\n```\n", lang),
+            &config::Markdown::default(),
+            &highlighter,
+            None,
+        );
+        assert!(html.contains(keyword), "{}", html);
+        assert!(html.contains("<span class=\"comment line toy\"># one</span>"));
+    }
+    let html = highlight_code(
+        "let x = 1\n",
+        Some(&"toy".to_string()),
+        &fixture_dir.join("example.toy"),
+        &highlighter,
+    )
+    .unwrap();
+    assert!(html.contains(keyword), "{}", html);
+    let html = highlight_code(
+        "let x = 1\n",
+        Some(&"Toy".to_string()),
+        &fixture_dir.join("example"),
+        &highlighter,
+    )
+    .unwrap();
+    assert!(html.contains(keyword), "{}", html);
+
+    // Themes in `themes/` can be selected by their names.
+    let highlighter = Highlighter::load(
+        &fixture_dir,
+        &config::Highlight {
+            theme: "Salmon".to_string(),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    let html = highlight_code(
+        "let x = 1\n",
+        Some(&"toy".to_string()),
+        &fixture_dir.join("example.toy"),
+        &highlighter,
+    )
+    .unwrap();
+    assert!(
+        html.starts_with("<pre style=\"background-color:#fff5ee;\">"),
+        "{}",
+        html
+    );
+    assert!(
+        html.contains("<span style=\"font-weight:bold;color:#fa8072;\">let</span>"),
+        "{}",
+        html
+    );
+    assert!(Highlighter::load(
+        &fixture_dir,
+        &config::Highlight {
+            theme: "Unknown".to_string(),
+            ..Default::default()
+        },
+    )
+    .is_err());
+}
+
+#[test]
+fn test_highlighter_stylesheet() {
+    let mut highlight_config = config::Highlight::default();
+    let highlighter = Highlighter::load(&PathBuf::from("example"), &highlight_config).unwrap();
+    assert!(highlighter.stylesheet().is_none());
+
+    highlight_config.classes = true;
+    highlight_config.dark_theme = Some("Solarized (dark)".to_string());
+    let highlighter = Highlighter::load(&PathBuf::from("example"), &highlight_config).unwrap();
+    let css = highlighter.stylesheet().unwrap();
+    assert!(css.starts_with(".highlight {\n  color: #657b83;\n  background-color: #fdf6e3;\n}\n"));
+    assert!(css.contains(
+        "@media (prefers-color-scheme: dark) {\n.highlight {\n  color: #839496;\n  background-color: #002b36;\n}\n"
+    ));
+
+    highlight_config.theme = "Unknown".to_string();
+    assert!(Highlighter::load(&PathBuf::from("example"), &highlight_config).is_err());
+}
+
+#[test]
+fn test_highlighter_cache() {
+    let cache_path = std::env::temp_dir().join(format!(
+        "salmon-test-highlight-cache-{}.json",
+        std::process::id()
+    ));
+    let _ = std::fs::remove_file(&cache_path);
+    let highlight_config = config::Highlight::default();
+    let path = PathBuf::from("example.rb");
+
+    let mut highlighter = Highlighter::load(&PathBuf::from("example"), &highlight_config).unwrap();
+    highlighter.load_cache(cache_path.clone());
+    let html = highlight_code("puts 1\n", None, &path, &highlighter).unwrap();
+    highlighter.save_cache().unwrap();
+
+    // Highlighted HTML comes from the cache, which is rewritten with used entries only.
+    let mut highlighter = Highlighter::load(&PathBuf::from("example"), &highlight_config).unwrap();
+    highlighter.load_cache(cache_path.clone());
+    assert_eq!(1, highlighter.cache.entries.len());
+    let cached_html = highlighter
+        .cache
+        .entries
+        .values()
+        .next()
+        .unwrap()
+        .html
+        .clone();
+    assert_eq!(html, cached_html);
+    highlight_code("puts 2\n", None, &path, &highlighter).unwrap();
+    highlighter.save_cache().unwrap();
+
+    // `puts 1` is dropped by the last build which did not highlight it.
+    let mut highlighter = Highlighter::load(&PathBuf::from("example"), &highlight_config).unwrap();
+    highlighter.load_cache(cache_path.clone());
+    let contents: Vec<_> = highlighter
+        .cache
+        .entries
+        .values()
+        .map(|e| e.content.as_str())
+        .collect();
+    assert_eq!(vec!["puts 2\n"], contents);
+
+    // The cache made with another theme is ignored.
+    let highlight_config = config::Highlight {
+        classes: true,
+        ..Default::default()
+    };
+    let mut highlighter = Highlighter::load(&PathBuf::from("example"), &highlight_config).unwrap();
+    highlighter.load_cache(cache_path.clone());
+    assert!(highlighter.cache.entries.is_empty());
+
+    // An entry of other code with the same hash is not used.
+    let mut highlighter = Highlighter::load(&PathBuf::from("example"), &highlight_config).unwrap();
+    let html = highlight_code("puts 1\n", None, &path, &highlighter).unwrap();
+    let mut entries = highlighter.highlighted.lock().unwrap().clone();
+    for entry in entries.values_mut() {
+        entry.content = "puts 2\n".to_string();
+        entry.html = "<pre>puts 2</pre>".to_string();
+    }
+    highlighter.cache.entries = entries;
+    assert_eq!(
+        html,
+        highlight_code("puts 1\n", None, &path, &highlighter).unwrap()
+    );
+
+    std::fs::remove_file(&cache_path).unwrap();
 }
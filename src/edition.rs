@@ -0,0 +1,123 @@
+use crate::article::{group_articles, Article, ArticlesByMonth, ArticlesByTag, ArticlesByYear};
+use crate::config::Config;
+use crate::layout::Layouts;
+use crate::taxonomy::{load_taxonomies, Taxonomy};
+use failure::Error;
+use std::rc::Rc;
+
+// A set of articles which has its own index, taxonomy pages and feed under `prefix`. The root
+// edition has all articles, and each language in `blog.languages` config has another one.
+#[derive(Debug)]
+pub struct Edition {
+    pub lang: Option<String>,
+    pub name: Option<String>,
+    // The prefix of all paths such as `/en`, which is empty for the root edition.
+    pub prefix: String,
+    pub sorted_articles: Vec<Rc<Article>>,
+    pub articles_by_tag: ArticlesByTag,
+    pub articles_by_year: ArticlesByYear,
+    pub articles_by_month: ArticlesByMonth,
+    pub taxonomies: Vec<Taxonomy>,
+}
+
+impl Edition {
+    fn load(
+        config: &Config,
+        layouts: &Layouts,
+        lang: Option<(&String, &str)>,
+        prefix: String,
+        sorted_articles: Vec<Rc<Article>>,
+    ) -> Result<Self, Error> {
+        let (articles_by_tag, articles_by_year, articles_by_month) =
+            group_articles(&sorted_articles);
+        let taxonomies = load_taxonomies(
+            config,
+            layouts,
+            &prefix,
+            &articles_by_tag,
+            &articles_by_year,
+            &articles_by_month,
+            &sorted_articles,
+        )?;
+
+        Ok(Edition {
+            lang: lang.map(|(l, _)| l.clone()),
+            name: lang.map(|(_, n)| n.to_string()),
+            prefix,
+            sorted_articles,
+            articles_by_tag,
+            articles_by_year,
+            articles_by_month,
+            taxonomies,
+        })
+    }
+
+    // Whether an article page is built with this edition. Articles without `lang` belong to the
+    // root edition.
+    pub fn owns(&self, article: &Article) -> bool {
+        article.lang == self.lang
+    }
+
+    // The URL of the `page_number`th index page (1 origin).
+    pub fn index_path(&self, page_number: usize) -> String {
+        if page_number == 1 {
+            format!("{}/", self.prefix)
+        } else {
+            format!("{}/page/{}/", self.prefix, page_number)
+        }
+    }
+
+    pub fn feed_path(&self) -> String {
+        format!("{}/feed.xml", self.prefix)
+    }
+}
+
+// The root edition comes first, followed by editions of languages sorted by their keys.
+// Languages without articles have no edition.
+pub fn load_editions(
+    config: &Config,
+    layouts: &Layouts,
+    sorted_articles: &[Rc<Article>],
+) -> Result<Vec<Edition>, Error> {
+    let blog_config = match config {
+        Config::V1(c) => &c.blog,
+    };
+
+    let mut editions = vec![Edition::load(
+        config,
+        layouts,
+        None,
+        String::new(),
+        sorted_articles.to_vec(),
+    )?];
+
+    let mut langs: Vec<_> = blog_config.languages.keys().collect();
+    langs.sort();
+    for lang in langs {
+        let language = &blog_config.languages[lang];
+        let prefix = language.prefix(lang);
+        if prefix.is_empty() {
+            return Err(failure::format_err!(
+                "Prefix of language `{}` must not be `/`.",
+                lang
+            ));
+        }
+        let articles: Vec<_> = sorted_articles
+            .iter()
+            .filter(|a| a.lang.as_ref() == Some(lang))
+            .cloned()
+            .collect();
+        if articles.is_empty() {
+            continue;
+        }
+        editions.push(Edition::load(
+            config,
+            layouts,
+            Some((lang, &language.name)),
+            prefix,
+            articles,
+        )?);
+    }
+
+    Ok(editions)
+}
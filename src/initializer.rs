@@ -22,6 +22,7 @@ impl Initializer {
         self.create_example_resources()?;
         self.create_example_salmon_config()?;
         self.create_docker_compose_file()?;
+        self.create_gitignore()?;
 
         log::info!("Your new Salmon project has been initialized!");
         log::info!(
@@ -74,18 +75,30 @@ impl Initializer {
         log::info!("Writing /layouts/article.hbs");
         let mut article_file = File::create(layout_dir.join("article.hbs"))?;
         article_file.write_all(include_bytes!("../example/layouts/article.hbs"))?;
+        log::info!("Writing /layouts/author.hbs");
+        let mut author_file = File::create(layout_dir.join("author.hbs"))?;
+        author_file.write_all(include_bytes!("../example/layouts/author.hbs"))?;
         log::info!("Writing /layouts/index.hbs");
         let mut index_file = File::create(layout_dir.join("index.hbs"))?;
         index_file.write_all(include_bytes!("../example/layouts/index.hbs"))?;
+        log::info!("Writing /layouts/month.hbs");
+        let mut month_file = File::create(layout_dir.join("month.hbs"))?;
+        month_file.write_all(include_bytes!("../example/layouts/month.hbs"))?;
         log::info!("Writing /layouts/page.hbs");
         let mut page_file = File::create(layout_dir.join("page.hbs"))?;
         page_file.write_all(include_bytes!("../example/layouts/page.hbs"))?;
         log::info!("Writing /layouts/rss.hbs");
         let mut rss_file = File::create(layout_dir.join("rss.hbs"))?;
         rss_file.write_all(include_bytes!("../example/layouts/rss.hbs"))?;
+        log::info!("Writing /layouts/series.hbs");
+        let mut series_file = File::create(layout_dir.join("series.hbs"))?;
+        series_file.write_all(include_bytes!("../example/layouts/series.hbs"))?;
         log::info!("Writing /layouts/tag.hbs");
         let mut tag_file = File::create(layout_dir.join("tag.hbs"))?;
         tag_file.write_all(include_bytes!("../example/layouts/tag.hbs"))?;
+        log::info!("Writing /layouts/tags.hbs");
+        let mut tags_file = File::create(layout_dir.join("tags.hbs"))?;
+        tags_file.write_all(include_bytes!("../example/layouts/tags.hbs"))?;
         log::info!("Writing /layouts/year.hbs");
         let mut year_file = File::create(layout_dir.join("year.hbs"))?;
         year_file.write_all(include_bytes!("../example/layouts/year.hbs"))?;
@@ -155,4 +168,13 @@ impl Initializer {
 
         Ok(())
     }
+
+    // Built files and the highlight cache are not sources of the project.
+    fn create_gitignore(&self) -> Result<(), Error> {
+        log::info!("Writing /.gitignore");
+        let mut gitignore_file = File::create(self.project_dir.join(".gitignore"))?;
+        gitignore_file.write_all(include_bytes!("../example/.gitignore"))?;
+
+        Ok(())
+    }
 }
@@ -1,4 +1,5 @@
 use failure::Error;
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::prelude::*;
 use std::path::PathBuf;
@@ -11,6 +12,10 @@ pub enum Layout {
     Year(String),
     Page(String),
     Rss(String),
+    Series(String),
+    Author(String),
+    Month(String),
+    Custom(String),
 }
 
 #[derive(Debug)]
@@ -21,8 +26,50 @@ pub struct Layouts {
     pub year: Layout,
     pub page: Layout,
     pub rss: Layout,
+    // Optional because only blogs which have series or authors need them.
+    pub series: Option<Layout>,
+    pub author: Option<Layout>,
+    // Optional because monthly archives are built only if it exists.
+    pub month: Option<Layout>,
+    // Any other layout files indexed by their names without `.hbs`, such as layouts of taxonomies.
+    pub customs: HashMap<String, Layout>,
 }
 
+impl Layouts {
+    // Find a layout by the name of its file without `.hbs`.
+    pub fn find(&self, name: &str) -> Option<&String> {
+        let layout = match name {
+            "index" => Some(&self.index),
+            "article" => Some(&self.article),
+            "tag" => Some(&self.tag),
+            "year" => Some(&self.year),
+            "page" => Some(&self.page),
+            "rss" => Some(&self.rss),
+            "series" => self.series.as_ref(),
+            "author" => self.author.as_ref(),
+            "month" => self.month.as_ref(),
+            _ => self.customs.get(name),
+        };
+        match layout {
+            Some(Layout::Index(s))
+            | Some(Layout::Article(s))
+            | Some(Layout::Tag(s))
+            | Some(Layout::Year(s))
+            | Some(Layout::Page(s))
+            | Some(Layout::Rss(s))
+            | Some(Layout::Series(s))
+            | Some(Layout::Author(s))
+            | Some(Layout::Month(s))
+            | Some(Layout::Custom(s)) => Some(s),
+            None => None,
+        }
+    }
+}
+
+const BUILTIN_LAYOUTS: [&str; 9] = [
+    "index", "article", "tag", "year", "page", "rss", "series", "author", "month",
+];
+
 pub fn load_layouts(src_dir: &PathBuf) -> Result<Layouts, Error> {
     log::info!("Start to load layout files.");
     Ok(Layouts {
@@ -32,6 +79,10 @@ pub fn load_layouts(src_dir: &PathBuf) -> Result<Layouts, Error> {
         year: load_year(src_dir)?,
         page: load_page(src_dir)?,
         rss: load_rss(src_dir)?,
+        series: load_series(src_dir)?,
+        author: load_author(src_dir)?,
+        month: load_month(src_dir)?,
+        customs: load_customs(src_dir)?,
     })
 }
 
@@ -59,6 +110,45 @@ fn load_rss(src_dir: &PathBuf) -> Result<Layout, Error> {
     Ok(Layout::Rss(load_file(src_dir, "rss.hbs")?))
 }
 
+fn load_series(src_dir: &PathBuf) -> Result<Option<Layout>, Error> {
+    if src_dir.join("layouts").join("series.hbs").exists() {
+        Ok(Some(Layout::Series(load_file(src_dir, "series.hbs")?)))
+    } else {
+        Ok(None)
+    }
+}
+
+fn load_author(src_dir: &PathBuf) -> Result<Option<Layout>, Error> {
+    if src_dir.join("layouts").join("author.hbs").exists() {
+        Ok(Some(Layout::Author(load_file(src_dir, "author.hbs")?)))
+    } else {
+        Ok(None)
+    }
+}
+
+fn load_month(src_dir: &PathBuf) -> Result<Option<Layout>, Error> {
+    if src_dir.join("layouts").join("month.hbs").exists() {
+        Ok(Some(Layout::Month(load_file(src_dir, "month.hbs")?)))
+    } else {
+        Ok(None)
+    }
+}
+
+fn load_customs(src_dir: &PathBuf) -> Result<HashMap<String, Layout>, Error> {
+    let mut customs = HashMap::new();
+    let layout_glob = glob::glob(src_dir.join("layouts/*.hbs").to_str().unwrap())?;
+    for entry in layout_glob {
+        let path = entry?;
+        let name = match path.file_stem().and_then(|s| s.to_str()) {
+            Some(name) if !BUILTIN_LAYOUTS.contains(&name) => name.to_string(),
+            _ => continue,
+        };
+        let file_name = format!("{}.hbs", name);
+        customs.insert(name, Layout::Custom(load_file(src_dir, &file_name)?));
+    }
+    Ok(customs)
+}
+
 fn load_file(src_dir: &PathBuf, file_name: &str) -> Result<String, Error> {
     let file_path = src_dir.join("layouts").join(file_name);
     log::info!("Loading a layout: {:?}", file_path);
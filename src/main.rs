@@ -3,16 +3,18 @@ pub mod blog;
 pub mod code;
 pub mod config;
 pub mod converter;
+pub mod edition;
 pub mod initializer;
 pub mod layout;
 pub mod page;
 pub mod paginator;
 pub mod partial;
 pub mod resource;
+pub mod taxonomy;
 pub mod template_generator;
 pub mod view_helper;
 
-use crate::blog::Blog;
+use crate::blog::{Blog, BuildOptions};
 use crate::config::Config;
 use crate::initializer::Initializer;
 
@@ -38,6 +40,16 @@ fn main() -> Result<(), failure::Error> {
                     clap::Arg::with_name("DEST_DIR")
                         .help("Specify a destination directory to put built files")
                         .index(2),
+                )
+                .arg(
+                    clap::Arg::with_name("drafts")
+                        .long("drafts")
+                        .help("Specify this if you want to build articles marked as draft"),
+                )
+                .arg(
+                    clap::Arg::with_name("future")
+                        .long("future")
+                        .help("Specify this if you want to build articles dated in the future"),
                 ),
         )
         .subcommand(
@@ -126,8 +138,16 @@ fn main() -> Result<(), failure::Error> {
             });
 
             let config = Config::load(&canonicalized_src_dir)?;
-            let init_blog_result =
-                Blog::init(canonicalized_src_dir, canonicalized_dest_dir, config);
+            let options = BuildOptions {
+                drafts: m.subcommand_matches("build").unwrap().is_present("drafts"),
+                future: m.subcommand_matches("build").unwrap().is_present("future"),
+            };
+            let init_blog_result = Blog::init(
+                canonicalized_src_dir,
+                canonicalized_dest_dir,
+                config,
+                options,
+            );
             match init_blog_result {
                 Ok(blog) => blog.build().unwrap(),
                 Err(e) => {
@@ -1,6 +1,9 @@
-use crate::converter;
+use crate::article::{decompose_source, FrontMatterError};
+use crate::config::Config;
+use crate::converter::{self, Highlighter};
 use failure::Error;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::prelude::*;
 use std::path::PathBuf;
@@ -8,11 +11,62 @@ use std::path::PathBuf;
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Page {
     pub path: PathBuf,
+    title: Option<String>,
+    description: Option<String>,
+    pub layout: Option<String>,
+    pub menu: Option<String>,
+    pub menu_order: Option<i64>,
     body: String,
-    html: String,
+    pub html: String,
+    extra: HashMap<String, serde_json::Value>,
 }
 
-pub fn load_pages(src_dir: &PathBuf) -> Result<Vec<Page>, Error> {
+// Front matter of a page is optional, and so are all of its keys.
+#[derive(Debug, Default, Deserialize)]
+struct FrontMatter {
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    layout: Option<String>,
+    // The name of the page in `menu_pages` given to layouts.
+    #[serde(default)]
+    menu: Option<String>,
+    #[serde(default)]
+    menu_order: Option<i64>,
+    // Keys which are not known by Salmon are kept to be used in layouts as `page.extra.*`.
+    #[serde(flatten)]
+    extra: HashMap<String, serde_json::Value>,
+}
+
+// A page without front matter may start with a `---` thematic break. It is not front matter
+// when it is followed by a blank line, is not closed, or encloses text which is not YAML mapping.
+fn decompose_page(source: String) -> Result<(FrontMatter, String), FrontMatterError> {
+    let mut lines = source.lines();
+    match (lines.next(), lines.next()) {
+        (_, Some(line)) if line.trim().is_empty() => return Ok((FrontMatter::default(), source)),
+        // Empty front matter, which serde_yaml cannot parse.
+        (Some(first), Some(second)) if first.trim() == "---" && second.trim() == "---" => {
+            let body = lines.map(|l| format!("{}\n", l)).collect();
+            return Ok((FrontMatter::default(), body));
+        }
+        _ => {}
+    }
+    match decompose_source::<serde_yaml::Value>(&source) {
+        Ok((serde_yaml::Value::Mapping(_), _)) => decompose_source::<FrontMatter>(&source),
+        Ok(_) | Err(FrontMatterError::NotFound) | Err(FrontMatterError::NotClosed { .. }) => {
+            Ok((FrontMatter::default(), source))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+pub fn load_pages(
+    src_dir: &PathBuf,
+    config: &Config,
+    highlighter: &Highlighter,
+) -> Result<Vec<Page>, Error> {
     let mut pages = Vec::new();
 
     let page_dir_glob = glob::glob(&src_dir.join("pages/**/*.md").to_str().unwrap())?;
@@ -20,7 +74,7 @@ pub fn load_pages(src_dir: &PathBuf) -> Result<Vec<Page>, Error> {
     for entry in page_dir_glob {
         match entry {
             Ok(path) => {
-                pages.push(load_page(&path, src_dir)?);
+                pages.push(load_page(&path, src_dir, config, highlighter)?);
                 log::debug!("Page \"{:?}\" has been loaded.", pages.last().unwrap().path);
             }
             Err(e) => return Err(failure::format_err!("{:?}", e)),
@@ -30,18 +84,68 @@ pub fn load_pages(src_dir: &PathBuf) -> Result<Vec<Page>, Error> {
     Ok(pages)
 }
 
-fn load_page(page_path: &PathBuf, src_dir: &PathBuf) -> Result<Page, Error> {
+fn load_page(
+    page_path: &PathBuf,
+    src_dir: &PathBuf,
+    config: &Config,
+    highlighter: &Highlighter,
+) -> Result<Page, Error> {
     let mut file = File::open(page_path)?;
-    let mut body = String::new();
-    file.read_to_string(&mut body)?;
+    let mut source = String::new();
+    file.read_to_string(&mut source)?;
+
+    let (front_matter, body) =
+        decompose_page(source).map_err(|e| failure::format_err!("{} in {:?}", e, page_path))?;
 
-    let html = converter::convert_to_html(&body);
+    let blog_config = match config {
+        Config::V1(ref c) => &c.blog,
+    };
+    let (html, _, _) = converter::convert_to_html(&body, &blog_config.markdown, highlighter, None);
 
     Ok(Page {
         path: page_path
             .strip_prefix(src_dir.join("pages/"))?
             .to_path_buf(),
+        title: front_matter.title,
+        description: front_matter.description,
+        layout: front_matter.layout,
+        menu: front_matter.menu,
+        menu_order: front_matter.menu_order,
         body: body,
         html: html,
+        extra: front_matter.extra,
     })
 }
+
+#[test]
+fn test_decompose_page() {
+    let (front_matter, body) =
+        decompose_page("---\ntitle: About\nmenu: About\n---\n\n# About\n".to_string()).unwrap();
+    assert_eq!(Some("About".to_string()), front_matter.title);
+    assert_eq!(Some("About".to_string()), front_matter.menu);
+    assert_eq!("\n# About\n", body);
+
+    let (front_matter, body) = decompose_page("# About\n".to_string()).unwrap();
+    assert_eq!(None, front_matter.title);
+    assert_eq!("# About\n", body);
+
+    let (front_matter, body) = decompose_page("---\n\n# About\n".to_string()).unwrap();
+    assert_eq!(None, front_matter.title);
+    assert_eq!("---\n\n# About\n", body);
+
+    // Thematic breaks around text are not front matter.
+    for source in &["---\n\nIntro\n\n---\n\nMore\n", "---\nIntro\n---\n\nMore\n"] {
+        let (front_matter, body) = decompose_page(source.to_string()).unwrap();
+        assert_eq!(None, front_matter.title);
+        assert_eq!(*source, body);
+    }
+    let (front_matter, body) =
+        decompose_page("---\n\nNote: it is not YAML.\n\n---\n".to_string()).unwrap();
+    assert_eq!(None, front_matter.title);
+    assert_eq!("---\n\nNote: it is not YAML.\n\n---\n", body);
+
+    let (_, body) = decompose_page("---\n---\n# About\n".to_string()).unwrap();
+    assert_eq!("# About\n", body);
+
+    assert!(decompose_page("---\ntitle: [About\n---\n".to_string()).is_err());
+}
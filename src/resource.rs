@@ -13,6 +13,14 @@ pub struct GeneralResource {
     pub dest_path: PathBuf,
 }
 
+// A file made by Salmon itself such as the stylesheet for highlighted code, which is not loaded
+// from `resources/`.
+#[derive(Debug)]
+pub struct GeneratedResource {
+    pub dest_path: PathBuf,
+    pub content: String,
+}
+
 #[derive(Debug)]
 pub struct Sass {
     pub src_path: PathBuf,
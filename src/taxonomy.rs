@@ -0,0 +1,417 @@
+use crate::article::{
+    normalize_tag, validate_term_slug, Article, ArticlesByMonth, ArticlesByTag, ArticlesByYear,
+    FRONT_MATTER_KEYS,
+};
+use crate::config::{self, Config};
+use crate::converter;
+use crate::layout::Layouts;
+use failure::Error;
+use std::rc::Rc;
+
+// A way to group articles by terms. Tags and years are built-in taxonomies, and others are
+// declared in `blog.taxonomies` config.
+#[derive(Debug)]
+pub struct Taxonomy {
+    pub name: String,
+    pub layout: String,
+    pub list_layout: Option<String>,
+    permalink: String,
+    // The prefix of all paths, such as `/en` for articles in a language. It is empty by default.
+    pub prefix: String,
+    pub entries_per_page: u32,
+    // The name of the variable which has the term in the layout, kept for layouts written before
+    // taxonomies, such as `tag_name`.
+    pub term_variable: Option<&'static str>,
+    pub terms: Vec<Term>,
+}
+
+#[derive(Debug)]
+pub struct Term {
+    pub key: String,
+    // The URL-safe form of the key, used for paths of term pages.
+    pub slug: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub articles: Vec<Rc<Article>>,
+}
+
+impl Taxonomy {
+    // The URL of the `page_number`th page (1 origin) of a term.
+    pub fn term_path(&self, term: &Term, page_number: usize) -> String {
+        let path = format!(
+            "{}{}",
+            self.prefix,
+            self.permalink.replace(":term", &term.slug)
+        );
+        if page_number == 1 {
+            format!("{}.html", path)
+        } else {
+            format!("{}/page/{}.html", path, page_number)
+        }
+    }
+
+    // A term passed to layouts.
+    pub fn term_view(&self, term: &Term) -> serde_json::Map<String, serde_json::Value> {
+        let mut m = serde_json::Map::new();
+        m.insert("key".to_string(), serde_json::json!(term.key));
+        m.insert("slug".to_string(), serde_json::json!(term.slug));
+        m.insert("name".to_string(), serde_json::json!(term.name));
+        m.insert(
+            "description".to_string(),
+            serde_json::json!(term.description),
+        );
+        m.insert("len".to_string(), serde_json::json!(term.articles.len()));
+        m.insert(
+            "path".to_string(),
+            serde_json::json!(self.term_path(term, 1)),
+        );
+        m
+    }
+
+    // The URL of the page listing all terms.
+    pub fn list_path(&self) -> String {
+        format!("{}/{}/index.html", self.prefix, self.name)
+    }
+}
+
+pub fn load_taxonomies(
+    config: &Config,
+    layouts: &Layouts,
+    prefix: &str,
+    articles_by_tag: &ArticlesByTag,
+    articles_by_year: &ArticlesByYear,
+    articles_by_month: &ArticlesByMonth,
+    sorted_articles: &[Rc<Article>],
+) -> Result<Vec<Taxonomy>, Error> {
+    let blog_config = match config {
+        Config::V1(c) => &c.blog,
+    };
+
+    // `articles_by_tag` is keyed by slugs, and the other attributes are taken from articles.
+    let mut tags: Vec<_> = articles_by_tag
+        .iter()
+        .map(|(slug, articles)| {
+            let tag = articles[0].tags.iter().find(|t| &t.slug == slug).unwrap();
+            Term {
+                key: tag.key.clone(),
+                slug: slug.clone(),
+                name: tag.name.clone(),
+                description: tag.description.clone(),
+                articles: articles.clone(),
+            }
+        })
+        .collect();
+    tags.sort_by(|t, u| t.slug.cmp(&u.slug));
+    let mut years: Vec<_> = articles_by_year.iter().collect();
+    years.sort_by(|(y1, _), (y2, _)| y2.cmp(y1));
+
+    let mut taxonomies = vec![
+        Taxonomy {
+            name: "tags".to_string(),
+            layout: "tag".to_string(),
+            list_layout: find_list_layout(layouts, "tags"),
+            permalink: "/tags/:term".to_string(),
+            prefix: prefix.to_string(),
+            entries_per_page: blog_config.tag_page.entries_per_page,
+            term_variable: Some("tag_name"),
+            terms: tags,
+        },
+        Taxonomy {
+            name: "years".to_string(),
+            layout: "year".to_string(),
+            list_layout: find_list_layout(layouts, "years"),
+            permalink: "/:term".to_string(),
+            prefix: prefix.to_string(),
+            entries_per_page: blog_config.year_page.entries_per_page,
+            term_variable: Some("year_num"),
+            terms: years
+                .into_iter()
+                .map(|(year, articles)| Term {
+                    key: year.to_string(),
+                    slug: year.to_string(),
+                    name: year.to_string(),
+                    description: None,
+                    articles: articles.clone(),
+                })
+                .collect(),
+        },
+    ];
+
+    // Monthly archives are optional not to require a new layout for existing projects.
+    if layouts.find("month").is_some() {
+        let mut months: Vec<_> = articles_by_month.iter().collect();
+        months.sort_by(|(m1, _), (m2, _)| m2.cmp(m1));
+        taxonomies.push(Taxonomy {
+            name: "months".to_string(),
+            layout: "month".to_string(),
+            list_layout: find_list_layout(layouts, "months"),
+            permalink: "/:term".to_string(),
+            prefix: prefix.to_string(),
+            entries_per_page: blog_config.month_page.entries_per_page,
+            term_variable: None,
+            terms: months
+                .into_iter()
+                .map(|((year, month), articles)| Term {
+                    key: format!("{}-{:02}", year, month),
+                    slug: format!("{}/{:02}", year, month),
+                    name: format!("{}/{:02}", year, month),
+                    description: None,
+                    articles: articles.clone(),
+                })
+                .collect(),
+        });
+    }
+
+    let mut names: Vec<_> = blog_config.taxonomies.keys().collect();
+    names.sort();
+    for name in names {
+        taxonomies.push(load_taxonomy(
+            name,
+            &blog_config.taxonomies[name],
+            prefix,
+            sorted_articles,
+        )?);
+    }
+
+    Ok(taxonomies)
+}
+
+// Built-in taxonomies have their list pages only when their layouts exist.
+fn find_list_layout(layouts: &Layouts, name: &str) -> Option<String> {
+    layouts.find(name).map(|_| name.to_string())
+}
+
+// Names of directories of pages built without `blog.taxonomies` config.
+const BUILT_IN_PAGES: &[&str] = &["tags", "years", "months", "series", "authors"];
+
+fn load_taxonomy(
+    name: &str,
+    taxonomy_config: &config::Taxonomy,
+    prefix: &str,
+    sorted_articles: &[Rc<Article>],
+) -> Result<Taxonomy, Error> {
+    if BUILT_IN_PAGES.contains(&name) {
+        return Err(failure::format_err!(
+            "`{}` is used by built-in pages and cannot be declared in `blog.taxonomies` config.",
+            name
+        ));
+    }
+    let permalink = match &taxonomy_config.permalink {
+        Some(permalink) => permalink.clone(),
+        None => format!("/{}/:term", name),
+    };
+    if !permalink.starts_with('/') || !permalink.contains(":term") {
+        return Err(failure::format_err!(
+            "Permalink of `{}` taxonomy must start with `/` and contain `:term`.",
+            name
+        ));
+    }
+    if let Some(dir) = permalink[1..].split('/').next() {
+        if BUILT_IN_PAGES.contains(&dir) {
+            return Err(failure::format_err!(
+                "Permalink of `{}` taxonomy must not be under `/{}/` for built-in pages.",
+                name,
+                dir
+            ));
+        }
+    }
+    let key = match &taxonomy_config.front_matter {
+        Some(key) => key.as_str(),
+        None => name,
+    };
+    if FRONT_MATTER_KEYS.contains(&key) {
+        return Err(failure::format_err!(
+            "Front matter `{}` is used by Salmon, so `{}` taxonomy cannot read it. Set another `front_matter`.",
+            key,
+            name
+        ));
+    }
+
+    // Terms are grouped by their slugs like tags, and different terms sharing a slug like `C` and
+    // `C++` are an error. `sorted_articles` is sorted by date, so are the
+    // articles of each term.
+    let mut terms: Vec<Term> = Vec::new();
+    for article in sorted_articles.iter() {
+        for term in extract_terms(article, key)? {
+            if !term.chars().any(char::is_alphanumeric) {
+                return Err(failure::format_err!(
+                    "Term `{}` of `{}` taxonomy in {:?} has no characters for its slug.",
+                    term,
+                    name,
+                    article.path
+                ));
+            }
+            let slug = converter::slugify(&term);
+            validate_term_slug(&slug).map_err(|e| {
+                failure::format_err!(
+                    "{} It is the slug of term `{}` of `{}` taxonomy in {:?}",
+                    e,
+                    term,
+                    name,
+                    article.path
+                )
+            })?;
+            let i = match terms.iter().position(|t| t.slug == slug) {
+                Some(i) if normalize_tag(&terms[i].key) != normalize_tag(&term) => {
+                    return Err(failure::format_err!(
+                        "Terms `{}` and `{}` of `{}` taxonomy have the same slug `{}` in {:?}.",
+                        terms[i].key,
+                        term,
+                        name,
+                        slug,
+                        article.path
+                    ));
+                }
+                Some(i) => i,
+                None => {
+                    terms.push(Term {
+                        key: term.clone(),
+                        slug,
+                        name: term,
+                        description: None,
+                        articles: Vec::new(),
+                    });
+                    terms.len() - 1
+                }
+            };
+            if !terms[i].articles.iter().any(|a| Rc::ptr_eq(a, article)) {
+                terms[i].articles.push(article.clone());
+            }
+        }
+    }
+    terms.sort_by(|t, u| t.slug.cmp(&u.slug));
+
+    Ok(Taxonomy {
+        name: name.to_string(),
+        layout: taxonomy_config.layout.clone(),
+        list_layout: taxonomy_config.list_layout.clone(),
+        permalink,
+        prefix: prefix.to_string(),
+        entries_per_page: taxonomy_config.entries_per_page,
+        term_variable: None,
+        terms,
+    })
+}
+
+// Terms are a string or a list of strings in the front matter.
+fn extract_terms(article: &Article, key: &str) -> Result<Vec<String>, Error> {
+    let invalid = || {
+        failure::format_err!(
+            "`{}` of {:?} must be a string or a list of strings.",
+            key,
+            article.path
+        )
+    };
+    match article.extra.get(key) {
+        None | Some(serde_json::Value::Null) => Ok(Vec::new()),
+        Some(serde_json::Value::String(term)) => Ok(vec![term.clone()]),
+        Some(serde_json::Value::Array(values)) => values
+            .iter()
+            .map(|v| v.as_str().map(|s| s.to_string()).ok_or_else(invalid))
+            .collect(),
+        Some(_) => Err(invalid()),
+    }
+}
+
+#[test]
+fn test_term_path() {
+    let taxonomy = Taxonomy {
+        name: "categories".to_string(),
+        layout: "category".to_string(),
+        list_layout: None,
+        permalink: "/categories/:term".to_string(),
+        prefix: String::new(),
+        entries_per_page: 15,
+        term_variable: None,
+        terms: Vec::new(),
+    };
+    let term = Term {
+        key: "Rust".to_string(),
+        slug: "rust".to_string(),
+        name: "Rust".to_string(),
+        description: None,
+        articles: Vec::new(),
+    };
+    assert_eq!("/categories/rust.html", taxonomy.term_path(&term, 1));
+    assert_eq!("/categories/rust/page/2.html", taxonomy.term_path(&term, 2));
+    assert_eq!("/categories/index.html", taxonomy.list_path());
+}
+
+#[test]
+fn test_load_taxonomy() {
+    let taxonomy_config = |yaml: &str| -> config::Taxonomy { serde_yaml::from_str(yaml).unwrap() };
+
+    let taxonomy = load_taxonomy(
+        "categories",
+        &taxonomy_config("layout: category"),
+        "/en",
+        &[],
+    )
+    .unwrap();
+    assert_eq!(
+        "/en/categories/:term",
+        format!("{}{}", taxonomy.prefix, taxonomy.permalink)
+    );
+
+    for name in &["tags", "series", "authors"] {
+        assert!(load_taxonomy(name, &taxonomy_config("layout: category"), "", &[]).is_err());
+    }
+    assert!(load_taxonomy(
+        "categories",
+        &taxonomy_config("{layout: category, permalink: '/series/:term'}"),
+        "",
+        &[]
+    )
+    .is_err());
+    assert!(load_taxonomy(
+        "writers",
+        &taxonomy_config("{layout: writer, front_matter: author}"),
+        "",
+        &[]
+    )
+    .is_err());
+    assert!(load_taxonomy(
+        "categories",
+        &taxonomy_config("{layout: category, permalink: 'categories/:term'}"),
+        "",
+        &[]
+    )
+    .is_err());
+
+    let article = |path: &str, terms: serde_json::Value| {
+        let mut article = Rc::try_unwrap(crate::article::test_article(
+            path,
+            "2019-06-23T00:00:00+09:00",
+            &[],
+        ))
+        .unwrap();
+        article.extra.insert("categories".to_string(), terms);
+        Rc::new(article)
+    };
+    let config = taxonomy_config("layout: category");
+    let taxonomy = load_taxonomy(
+        "categories",
+        &config,
+        "",
+        &[
+            article("/a.html", serde_json::json!(["Rust", "Web Server"])),
+            article("/b.html", serde_json::json!("rust")),
+        ],
+    )
+    .unwrap();
+    let terms: Vec<_> = taxonomy
+        .terms
+        .iter()
+        .map(|t| (t.slug.as_str(), t.articles.len()))
+        .collect();
+    assert_eq!(vec![("rust", 2), ("web-server", 1)], terms);
+
+    for terms in &[
+        serde_json::json!(["C", "C++"]),
+        serde_json::json!("+"),
+        serde_json::json!("Index"),
+    ] {
+        let articles = [article("/a.html", terms.clone())];
+        assert!(load_taxonomy("categories", &config, "", &articles).is_err());
+    }
+}
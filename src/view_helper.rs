@@ -1,4 +1,5 @@
-use handlebars::{Context, Handlebars, Helper, Output, RenderContext, RenderError};
+use crate::article::resolve_relative_path;
+use handlebars::{Context, Handlebars, Helper, HelperDef, Output, RenderContext, RenderError};
 use scraper::{Html, Selector};
 
 pub fn convert_to_iso8601(
@@ -8,14 +9,21 @@ pub fn convert_to_iso8601(
     _: &mut RenderContext,
     out: &mut Output,
 ) -> Result<(), RenderError> {
+    // The first param which is a string is used. It helps to fall back to another date when
+    // an optional one like `article.updated_datetime` is null.
     let date_str = h
-        .param(0)
-        .and_then(|v| v.value().as_str())
+        .params()
+        .iter()
+        .filter_map(|v| v.value().as_str())
+        .next()
         .ok_or(RenderError::new(
-            "convert_to_iso8601: Param 0 with string type is required.",
+            "convert_to_iso8601: At least one param with string type is required.",
         ))?;
 
-    if let Ok(date_iso8601) = chrono::NaiveDate::parse_from_str(date_str, "%Y-%m-%d") {
+    if let Ok(datetime) = chrono::DateTime::parse_from_rfc3339(date_str) {
+        out.write(datetime.to_rfc3339().as_str())?;
+        Ok(())
+    } else if let Ok(date_iso8601) = chrono::NaiveDate::parse_from_str(date_str, "%Y-%m-%d") {
         // NativeDate doesn't have timezone and time information. We should fill it with some value.
         out.write(
             date_iso8601
@@ -107,7 +115,26 @@ pub fn embed_code(
             "embed_code: Param 0 with string type is required.",
         ))?;
 
-    match ctx.data().get("codes").unwrap().get(path) {
+    // A relative path refers to a file in the bundle of the article.
+    let path = if path.starts_with('/') {
+        path.to_string()
+    } else {
+        let article_path = ctx
+            .data()
+            .get("article")
+            .and_then(|a| a.get("path"))
+            .and_then(|p| p.as_str())
+            .ok_or(RenderError::new(format!(
+                "embed_code: Relative path {} is available only in articles.",
+                path
+            )))?;
+        resolve_relative_path(article_path, path)
+    };
+
+    let codes = ctx.data().get("codes").ok_or(RenderError::new(
+        "embed_code: Codes are not available in this layout.",
+    ))?;
+    match codes.get(&path) {
         Some(code) => {
             out.write(code.get("highlighted_html").unwrap().as_str().unwrap())?;
         }
@@ -122,36 +149,78 @@ pub fn embed_code(
     Ok(())
 }
 
-pub fn summarize_article(
-    h: &Helper,
-    _: &Handlebars,
-    _: &Context,
-    _: &mut RenderContext,
-    out: &mut Output,
-) -> Result<(), RenderError> {
-    let article = h
-        .param(0)
-        .and_then(|v| v.value().as_object())
-        .ok_or(RenderError::new(
-            "summarize_article: Param 0 with JSON object type is required.",
-        ))?;
-    let is_escaped = h
-        .param(1)
-        .and_then(|v| v.value().as_bool())
-        .unwrap_or(false);
+// `summarize_article` writes `excerpt` of an article, which is the part before `<!-- more -->`.
+// If the article has no such marker, it writes top level elements of the article until
+// `elements` elements or `characters` characters of text are written. Handlebars in the summary
+// is evaluated like `article_html` partial.
+pub struct SummarizeArticle {
+    pub elements: usize,
+    pub characters: Option<usize>,
+}
 
-    let article_html = Html::parse_fragment(article.get("html").unwrap().as_str().unwrap());
-    let selector = Selector::parse("html > *").unwrap();
+impl HelperDef for SummarizeArticle {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        r: &'reg Handlebars,
+        ctx: &'rc Context,
+        _: &mut RenderContext<'reg>,
+        out: &mut dyn Output,
+    ) -> Result<(), RenderError> {
+        let article = h
+            .param(0)
+            .and_then(|v| v.value().as_object())
+            .ok_or(RenderError::new(
+                "summarize_article: Param 0 with JSON object type is required.",
+            ))?;
+        let is_escaped = h
+            .param(1)
+            .and_then(|v| v.value().as_bool())
+            .unwrap_or(false);
 
-    for p in article_html.select(&selector).take(4) {
+        let summary = match article.get("excerpt").and_then(|v| v.as_str()) {
+            Some(excerpt) => excerpt.to_string(),
+            None => self.summarize(article.get("html").unwrap().as_str().unwrap()),
+        };
+        // Relative paths of `embed_code` refer to the bundle of the summarized article. Only the
+        // variables for `embed_code` are given not to copy the whole context for each article.
+        let mut data = serde_json::Map::new();
+        data.insert(
+            "article".to_string(),
+            serde_json::Value::Object(article.clone()),
+        );
+        if let Some(codes) = ctx.data().get("codes") {
+            data.insert("codes".to_string(), codes.clone());
+        }
+        let summary = r
+            .render_template(&summary, &data)
+            .map_err(|e| RenderError::new(format!("summarize_article: {}", e)))?;
         if is_escaped {
-            out.write(&handlebars::html_escape(&p.html()))?;
+            out.write(&handlebars::html_escape(&summary))?;
         } else {
-            out.write(&p.html())?;
+            out.write(&summary)?;
         }
+
+        Ok(())
     }
+}
 
-    Ok(())
+impl SummarizeArticle {
+    fn summarize(&self, html: &str) -> String {
+        let article_html = Html::parse_fragment(html);
+        let selector = Selector::parse("html > *").unwrap();
+
+        let mut summary = String::new();
+        let mut num_chars = 0;
+        for p in article_html.select(&selector).take(self.elements) {
+            if self.characters.map(|c| num_chars >= c).unwrap_or(false) {
+                break;
+            }
+            summary.push_str(&p.html());
+            num_chars += p.text().map(|t| t.chars().count()).sum::<usize>();
+        }
+        summary
+    }
 }
 
 pub fn time_now(
@@ -190,3 +259,21 @@ fn test_build_full_url() {
         "https://example.com/foo/bar.jpg"
     );
 }
+
+#[test]
+fn test_summarize_article() {
+    let html = "<p>aaaaa</p>\n<p>bbbbb</p>\n<ul><li>ccccc</li></ul>\n<p>ddddd</p>\n<p>eeeee</p>\n";
+    let helper = SummarizeArticle {
+        elements: 4,
+        characters: None,
+    };
+    assert_eq!(
+        "<p>aaaaa</p><p>bbbbb</p><ul><li>ccccc</li></ul><p>ddddd</p>",
+        helper.summarize(html)
+    );
+    let helper = SummarizeArticle {
+        elements: 4,
+        characters: Some(8),
+    };
+    assert_eq!("<p>aaaaa</p><p>bbbbb</p>", helper.summarize(html));
+}